@@ -1,7 +1,10 @@
 use crate::heroes_info::Hero;
 use crate::heroes_info::HeroesInfo;
 use crate::match_stats::Match;
-use crate::match_stats::PlayerName;
+use crate::types::AccountId;
+use crate::CONFIG;
+use itertools::Itertools;
+use std::collections::HashSet;
 
 #[macro_export]
 macro_rules! skip_fail {
@@ -13,18 +16,64 @@ macro_rules! skip_fail {
     };
 }
 
+/// Abandoned matches are excluded from winratio stats, unless "include_abandoned_matches"
+/// config field is set.
+fn includes_abandoned() -> bool {
+    CONFIG
+        .get_bool("include_abandoned_matches")
+        .unwrap_or(false)
+}
+
+/// Matches taken into account by winratio based analysis.
+pub fn countable_matches<'a>(matches: &'a Vec<Match>) -> impl Iterator<Item = &'a Match> {
+    let include_abandoned = includes_abandoned();
+    matches
+        .iter()
+        .filter(move |match_| include_abandoned || !match_.is_abandoned())
+}
+
+/// Number of matches excluded from winratio based analysis.
+pub fn count_excluded_matches(matches: &Vec<Match>) -> u32 {
+    (matches.len() - countable_matches(matches).count()) as u32
+}
+
 /// Finds heroes played by players.
 pub fn get_heroes(
     heroes_info: &HeroesInfo,
     match_: &Match,
-    mut team: Vec<String>,
-) -> Vec<(PlayerName, Hero)> {
+    mut team: Vec<AccountId>,
+) -> Vec<(AccountId, Hero)> {
     let mut team_setup = vec![];
     team.sort();
     for player in team {
         let player_hero_id = skip_fail!(match_.get_player_hero(&player));
-        let hero = heroes_info.get_hero(player_hero_id);
+        let hero = heroes_info
+            .get_hero(player_hero_id)
+            .unwrap_or_else(|| Hero::unknown(player_hero_id));
         team_setup.push((player, hero));
     }
     team_setup
 }
+
+/// Finds ids of heroes played by members and other players, which are missing in heroes constants.
+pub fn find_unknown_heroes(heroes_info: &HeroesInfo, matches: &Vec<Match>) -> Vec<u64> {
+    let mut unknown_ids = HashSet::new();
+    for match_ in matches {
+        let mut hero_ids: Vec<u64> = match_
+            .get_other_heroes()
+            .iter()
+            .map(|other_hero| other_hero.get_hero_id())
+            .collect();
+        if let Ok(teams) = match_.get_teams() {
+            for player in teams.iter().flat_map(|team| team.get_players().clone()) {
+                hero_ids.extend(match_.get_player_hero(&player).ok());
+            }
+        }
+        for hero_id in hero_ids {
+            if heroes_info.get_hero(hero_id).is_none() {
+                unknown_ids.insert(hero_id);
+            }
+        }
+    }
+    unknown_ids.into_iter().sorted().collect()
+}
@@ -1,9 +1,8 @@
 use crate::analyzers::analyzers_utils::*;
 use crate::analyzers::WinRatio;
 use crate::heroes_info::HeroesInfo;
-use crate::match_stats::Match;
-use crate::match_stats::PlayerName;
-use crate::CONFIG;
+use crate::match_stats::{Match, MemberNames, PlayerName};
+use crate::types::AccountId;
 use itertools::Itertools;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -11,36 +10,26 @@ extern crate ordered_float;
 use ordered_float::OrderedFloat;
 
 pub type HeroName = String;
-pub type PlayerHeroScores = Vec<(Vec<(PlayerName, HeroName)>, WinRatio)>;
+pub type PlayerHeroScores = Vec<(Vec<(AccountId, HeroName)>, WinRatio)>;
 
 /// Extracts winratio stats for each Player-Hero team setup from match info.
-pub fn get_heroes_played(data: &Vec<Match>) -> PlayerHeroScores {
-    let heroes_info_filename = CONFIG.get_str("heroes_info_filename").unwrap();
-    let heroes_info = match HeroesInfo::init(heroes_info_filename) {
-        Ok(val) => val,
-        Err(e) => {
-            warn!(
-                "Can't load heroes_info from heroes.json: {}. Stoping analysis.",
-                e
-            );
-            return vec![];
-        }
-    };
-    let mut heroes_played: HashMap<Vec<(PlayerName, HeroName)>, WinRatio> = HashMap::new();
-    for match_ in data.iter() {
-        let team = skip_fail!(match_.get_team());
-        let team_setup = get_heroes(&heroes_info, match_, team);
-        let team_setup: Vec<(PlayerName, HeroName)> = team_setup
-            .into_iter()
-            .map(|(p, hero)| (p, hero.name))
-            .collect();
-        let is_won = skip_fail!(match_.is_won());
-        for i in 1..=(team_setup.len()) {
-            for team_combination in team_setup.clone().into_iter().combinations(i) {
-                heroes_played
-                    .entry(team_combination)
-                    .or_default()
-                    .add_score(is_won);
+/// Only teammates are combined into setups. Abandoned matches are skipped.
+pub fn get_heroes_played(data: &Vec<Match>, heroes_info: &HeroesInfo) -> PlayerHeroScores {
+    let mut heroes_played: HashMap<Vec<(AccountId, HeroName)>, WinRatio> = HashMap::new();
+    for match_ in countable_matches(data) {
+        for team in skip_fail!(match_.get_teams()) {
+            let team_setup = get_heroes(heroes_info, match_, team.get_players().clone());
+            let team_setup: Vec<(AccountId, HeroName)> = team_setup
+                .into_iter()
+                .map(|(p, hero)| (p, hero.name))
+                .collect();
+            for i in 1..=(team_setup.len()) {
+                for team_combination in team_setup.clone().into_iter().combinations(i) {
+                    heroes_played
+                        .entry(team_combination)
+                        .or_default()
+                        .add_score(team.is_won());
+                }
             }
         }
     }
@@ -63,55 +52,58 @@ pub struct HeroStats {
     common_player_relative: (PlayerName, WinRatio, f64),
 }
 
+/// Hero winratio of the player relative to their total winratio. Both are ranking scores,
+/// so players with few games on the hero don't get the top.
+fn relative_score(hero_wr: &WinRatio, total_wr: &WinRatio) -> f64 {
+    if total_wr.score() == 0. {
+        return 0.;
+    }
+    hero_wr.score() / total_wr.score()
+}
+
 /// Constructs HeroStats using players winratio on this hero and certain maps: Player -> WinRatio(total).
 fn aggregate_hero_stats(
     hero_name: String,
-    players_hero_wr: Vec<(PlayerName, WinRatio)>,
-    player_wr: &HashMap<PlayerName, WinRatio>,
+    players_hero_wr: Vec<(AccountId, WinRatio)>,
+    player_wr: &HashMap<AccountId, WinRatio>,
+    member_names: &MemberNames,
 ) -> HeroStats {
-    let top_player: (PlayerName, WinRatio, f64) = {
-        let (player_name, wr) = match players_hero_wr
-            .iter()
-            .filter(|(_, wr)| wr.total() > 5)
-            .max_by_key(|(player_name, wr)| {
-                OrderedFloat(wr.as_percent() / player_wr[player_name].as_percent())
-            }) {
-            Some(x) => x.clone(),
-            None => (String::new(), WinRatio::default()),
-        };
-        if player_name.is_empty() {
-            (String::new(), WinRatio::default(), 0.)
-        } else {
-            let hero_relative_winratio = wr.as_percent() / player_wr[&player_name].as_percent();
+    let top_player: (PlayerName, WinRatio, f64) = match players_hero_wr
+        .iter()
+        .filter(|(_, wr)| wr.total() > 5)
+        .max_by_key(|(account_id, wr)| OrderedFloat(relative_score(wr, &player_wr[account_id])))
+    {
+        Some((account_id, wr)) => {
+            let hero_relative_winratio = relative_score(wr, &player_wr[account_id]);
             let hero_relative_winratio = (hero_relative_winratio * 1000.).round() / 1000.;
-            (player_name, wr, hero_relative_winratio)
+            (
+                member_names.get(account_id),
+                wr.clone(),
+                hero_relative_winratio,
+            )
         }
+        None => (String::new(), WinRatio::default(), 0.),
     };
     let common_player_raw: (PlayerName, WinRatio) = match players_hero_wr
         .iter()
         .filter(|(_, wr)| wr.total() > 0)
         .max_by_key(|(_, wr)| wr.total())
     {
-        Some(x) => x.clone(),
+        Some((account_id, wr)) => (member_names.get(account_id), wr.clone()),
         None => (String::new(), WinRatio::default()),
     };
-    let common_player_relative = {
-        let (player_name, wr) = match players_hero_wr
-            .iter()
-            .filter(|(_, wr)| wr.total() > 0)
-            .max_by_key(|(player_name, wr)| {
-                OrderedFloat(wr.total() as f64 / player_wr[player_name].total() as f64)
-            }) {
-            Some(x) => x.clone(),
-            None => (String::new(), WinRatio::default()),
-        };
-        if player_name.is_empty() {
-            (String::new(), WinRatio::default(), 0.)
-        } else {
-            let hero_play_prcnt = wr.total() as f64 / player_wr[&player_name].total() as f64;
+    let common_player_relative = match players_hero_wr
+        .iter()
+        .filter(|(_, wr)| wr.total() > 0)
+        .max_by_key(|(account_id, wr)| {
+            OrderedFloat(wr.total() as f64 / player_wr[account_id].total() as f64)
+        }) {
+        Some((account_id, wr)) => {
+            let hero_play_prcnt = wr.total() as f64 / player_wr[account_id].total() as f64;
             let hero_play_prcnt = (hero_play_prcnt * 1000.).round() / 1000.;
-            (player_name, wr, hero_play_prcnt)
+            (member_names.get(account_id), wr.clone(), hero_play_prcnt)
         }
+        None => (String::new(), WinRatio::default(), 0.),
     };
     HeroStats {
         hero_name,
@@ -124,18 +116,18 @@ fn aggregate_hero_stats(
 /// Constructs map Hero -> PlayersWinratio.
 fn get_hero_players_wr(
     player_hero_scores: &PlayerHeroScores,
-) -> HashMap<HeroName, Vec<(PlayerName, WinRatio)>> {
+) -> HashMap<HeroName, Vec<(AccountId, WinRatio)>> {
     player_hero_scores
         .iter()
         .filter(|(heroes_played, _)| heroes_played.len() == 1)
         .fold(
-            HashMap::<HeroName, Vec<(PlayerName, WinRatio)>>::new(),
+            HashMap::<HeroName, Vec<(AccountId, WinRatio)>>::new(),
             |mut s, (heroes_played, wr)| {
                 let hero_name = heroes_played[0].1.clone();
-                let player_name = heroes_played[0].0.clone();
+                let account_id = heroes_played[0].0;
                 s.entry(hero_name)
                     .or_default()
-                    .push((player_name, wr.clone()));
+                    .push((account_id, wr.clone()));
                 s
             },
         )
@@ -144,16 +136,16 @@ fn get_hero_players_wr(
 /// Constructs map Player -> HeroesWinratio.
 fn get_player_heroes_wr(
     player_hero_scores: &PlayerHeroScores,
-) -> HashMap<PlayerName, Vec<(HeroName, WinRatio)>> {
+) -> HashMap<AccountId, Vec<(HeroName, WinRatio)>> {
     player_hero_scores
         .iter()
         .filter(|(heroes_played, _)| heroes_played.len() == 1)
         .fold(
-            HashMap::<PlayerName, Vec<(HeroName, WinRatio)>>::new(),
+            HashMap::<AccountId, Vec<(HeroName, WinRatio)>>::new(),
             |mut s, (heroes_played, wr)| {
                 let hero_name = heroes_played[0].1.clone();
-                let player_name = heroes_played[0].0.clone();
-                s.entry(player_name)
+                let account_id = heroes_played[0].0;
+                s.entry(account_id)
                     .or_default()
                     .push((hero_name, wr.clone()));
                 s
@@ -163,13 +155,13 @@ fn get_player_heroes_wr(
 
 /// Contructs Players total winratio map.
 fn get_player_wr(
-    player_heroes_wr: &HashMap<PlayerName, Vec<(HeroName, WinRatio)>>,
-) -> HashMap<PlayerName, WinRatio> {
+    player_heroes_wr: &HashMap<AccountId, Vec<(HeroName, WinRatio)>>,
+) -> HashMap<AccountId, WinRatio> {
     player_heroes_wr
         .iter()
-        .map(|(player_name, heroes_wr)| {
+        .map(|(account_id, heroes_wr)| {
             (
-                player_name.clone(),
+                *account_id,
                 heroes_wr
                     .iter()
                     .fold(WinRatio::default(), |wr, (_, hero_wr)| wr + hero_wr.clone()),
@@ -180,11 +172,12 @@ fn get_player_wr(
 
 /// For each player finds hero with highest winratio.
 fn get_player_stats(
-    player_heroes_wr: &HashMap<PlayerName, Vec<(HeroName, WinRatio)>>,
+    player_heroes_wr: &HashMap<AccountId, Vec<(HeroName, WinRatio)>>,
+    member_names: &MemberNames,
 ) -> Vec<(PlayerName, HeroName, WinRatio)> {
     player_heroes_wr
         .iter()
-        .map(|(player_name, heroes_wr)| {
+        .map(|(account_id, heroes_wr)| {
             let (hero_name, hero_wr) = match heroes_wr
                 .iter()
                 .filter(|(_, wr)| wr.total() > 15)
@@ -193,13 +186,20 @@ fn get_player_stats(
                 Some(x) => x.clone(),
                 None => (String::new(), WinRatio::default()),
             };
-            (player_name.clone(), hero_name.clone(), hero_wr.clone())
+            (
+                member_names.get(account_id),
+                hero_name.clone(),
+                hero_wr.clone(),
+            )
         })
         .collect()
 }
 
 /// Computes whole HeroPlayersStats from Player-Hero setups.
-pub fn get_hero_players_stats(player_hero_scores: &PlayerHeroScores) -> HeroPlayersStats {
+pub fn get_hero_players_stats(
+    player_hero_scores: &PlayerHeroScores,
+    member_names: &MemberNames,
+) -> HeroPlayersStats {
     let hero_players_wr = get_hero_players_wr(player_hero_scores);
     let player_heroes_wr = get_player_heroes_wr(player_hero_scores);
     let player_wr = get_player_wr(&player_heroes_wr);
@@ -209,10 +209,15 @@ pub fn get_hero_players_stats(player_hero_scores: &PlayerHeroScores) -> HeroPlay
             if players_hero_wr.is_empty() {
                 return None;
             };
-            Some(aggregate_hero_stats(hero_name, players_hero_wr, &player_wr))
+            Some(aggregate_hero_stats(
+                hero_name,
+                players_hero_wr,
+                &player_wr,
+                member_names,
+            ))
         })
         .collect();
-    let players_stats = get_player_stats(&player_heroes_wr);
+    let players_stats = get_player_stats(&player_heroes_wr, member_names);
     HeroPlayersStats {
         heroes_stats,
         players_stats,
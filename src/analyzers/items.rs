@@ -0,0 +1,238 @@
+use crate::analyzers::analyzers_utils::countable_matches;
+use crate::analyzers::heroes::HeroName;
+use crate::analyzers::WinRatio;
+use crate::heroes_info::{Hero, HeroesInfo};
+use crate::items_info::{Item, ItemsInfo};
+use crate::match_stats::{Match, MemberNames, PlayerName};
+use crate::types::AccountId;
+use crate::CONFIG;
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+use std::collections::HashMap;
+
+pub type ItemName = String;
+
+/// Number of most common core items shown per player-hero.
+const COMMON_ITEMS_COUNT: usize = 6;
+
+/// Winratio of games in which item was bought before and after the timing threshold,
+/// and of games in which it wasn't bought at all.
+#[derive(Serialize, Default, Clone)]
+pub struct ItemTimingSplit {
+    item_name: ItemName,
+    threshold: i64,
+    before: WinRatio,
+    after: WinRatio,
+    not_bought: WinRatio,
+}
+
+/// Item stats of a player on certain hero.
+#[derive(Serialize)]
+pub struct ItemBuild {
+    player_name: PlayerName,
+    hero_name: HeroName,
+    games: u32,
+    common_items: Vec<(ItemName, f64)>,
+    item_timings: Vec<(ItemName, f64)>,
+    items_wr: Vec<(ItemName, WinRatio)>,
+    timing_splits: Vec<ItemTimingSplit>,
+}
+
+/// Items stats accumulated over the games of a player on certain hero.
+#[derive(Default)]
+struct ItemBuildScore {
+    wr: WinRatio,
+    items_count: HashMap<String, u32>,
+    items_wr: HashMap<String, WinRatio>,
+    timings: HashMap<String, Vec<i64>>,
+    timing_splits: HashMap<String, ItemTimingSplit>,
+}
+
+/// Core items are ones costing at least "core_item_min_cost" gold.
+fn is_core_item(item: &Item) -> bool {
+    let min_cost = CONFIG.get_int("core_item_min_cost").unwrap_or(2000) as u64;
+    item.cost >= min_cost
+}
+
+/// Timing thresholds in seconds by item key, set by "item_timing_thresholds" config field.
+/// By default Black King Bar before and after 25 minutes.
+fn timing_thresholds() -> HashMap<String, i64> {
+    CONFIG.get("item_timing_thresholds").unwrap_or_else(|_| {
+        vec![("black_king_bar".to_string(), 1500)]
+            .into_iter()
+            .collect()
+    })
+}
+
+/// Average rounded to one decimal place.
+fn average(values: &Vec<i64>) -> f64 {
+    let avg = values.iter().sum::<i64>() as f64 / values.len() as f64;
+    (avg * 10.).round() / 10.
+}
+
+impl ItemBuildScore {
+    /// Adds single game of the player. Core items are taken from the final inventory,
+    /// timings from the purchase log, which is available only in parsed matches.
+    fn add_game(
+        &mut self,
+        match_: &Match,
+        account_id: &AccountId,
+        is_won: bool,
+        items_info: &ItemsInfo,
+        thresholds: &HashMap<String, i64>,
+    ) {
+        let player_stats = match match_.get_player_stats(account_id) {
+            Ok(player_stats) => player_stats,
+            Err(_) => return,
+        };
+        self.wr.add_score(is_won);
+        let core_items: Vec<String> = player_stats
+            .get_inventory()
+            .into_iter()
+            .map(|item_id| {
+                items_info
+                    .get_item(item_id)
+                    .unwrap_or_else(|| Item::unknown(item_id))
+            })
+            .filter(is_core_item)
+            .map(|item| item.key)
+            .unique()
+            .collect();
+        for item_key in core_items.iter() {
+            *self.items_count.entry(item_key.clone()).or_default() += 1;
+            self.items_wr
+                .entry(item_key.clone())
+                .or_default()
+                .add_score(is_won);
+        }
+        let purchase_log = match player_stats.get_purchase_log() {
+            Some(purchase_log) => purchase_log,
+            None => return,
+        };
+        let mut first_purchases: HashMap<&String, i64> = HashMap::new();
+        for purchase in purchase_log.iter() {
+            first_purchases
+                .entry(&purchase.key)
+                .or_insert(purchase.time);
+        }
+        for (item_key, time) in first_purchases.iter() {
+            let is_core = items_info
+                .get_item_by_key(item_key)
+                .map_or(false, |item| is_core_item(&item));
+            if is_core || thresholds.contains_key(*item_key) {
+                self.timings
+                    .entry(item_key.to_string())
+                    .or_default()
+                    .push(*time);
+            }
+        }
+        for (item_key, threshold) in thresholds.iter() {
+            let split = self
+                .timing_splits
+                .entry(item_key.clone())
+                .or_insert_with(|| ItemTimingSplit {
+                    item_name: item_key.clone(),
+                    threshold: *threshold,
+                    ..ItemTimingSplit::default()
+                });
+            match first_purchases.get(item_key) {
+                Some(time) if time < threshold => split.before.add_score(is_won),
+                Some(_) => split.after.add_score(is_won),
+                None => split.not_bought.add_score(is_won),
+            }
+        }
+    }
+
+    /// Converts accumulated scores to the result, with items named by their display names.
+    fn into_item_build(
+        self,
+        player_name: PlayerName,
+        hero_name: HeroName,
+        items_info: &ItemsInfo,
+    ) -> ItemBuild {
+        let item_name = |key: &String| -> ItemName {
+            items_info
+                .get_item_by_key(key)
+                .map_or(key.clone(), |item| item.name)
+        };
+        let games = self.wr.total();
+        let common_items = self
+            .items_count
+            .iter()
+            .sorted_by_key(|(key, count)| (std::cmp::Reverse(**count), (*key).clone()))
+            .take(COMMON_ITEMS_COUNT)
+            .map(|(key, count)| {
+                let frequency = (*count as f64 / games as f64 * 1000.).round() / 1000.;
+                (item_name(key), frequency)
+            })
+            .collect();
+        let item_timings = self
+            .timings
+            .iter()
+            .map(|(key, times)| (item_name(key), average(times)))
+            .sorted_by_key(|(_, avg)| OrderedFloat(*avg))
+            .collect();
+        let items_wr = self
+            .items_wr
+            .into_iter()
+            .map(|(key, wr)| (item_name(&key), wr))
+            .sorted_by(|(_, a), (_, b)| b.cmp(a))
+            .collect();
+        let timing_splits = self
+            .timing_splits
+            .into_iter()
+            .map(|(key, split)| ItemTimingSplit {
+                item_name: item_name(&key),
+                ..split
+            })
+            .collect();
+        ItemBuild {
+            player_name,
+            hero_name,
+            games,
+            common_items,
+            item_timings,
+            items_wr,
+            timing_splits,
+        }
+    }
+}
+
+/// Computes item stats for each Player-Hero pair played at least "min_item_build_games" times.
+/// Abandoned matches are skipped.
+pub fn get_item_builds(
+    matches: &Vec<Match>,
+    heroes_info: &HeroesInfo,
+    items_info: &ItemsInfo,
+    member_names: &MemberNames,
+) -> Vec<ItemBuild> {
+    let min_games = CONFIG.get_int("min_item_build_games").unwrap_or(5) as u32;
+    let thresholds = timing_thresholds();
+    let mut scores: HashMap<(AccountId, u64), ItemBuildScore> = HashMap::new();
+    for match_ in countable_matches(matches) {
+        for team in skip_fail!(match_.get_teams()) {
+            for account_id in team.get_players().clone() {
+                let hero_id = skip_fail!(match_.get_player_hero(&account_id));
+                scores.entry((account_id, hero_id)).or_default().add_game(
+                    match_,
+                    &account_id,
+                    team.is_won(),
+                    items_info,
+                    &thresholds,
+                );
+            }
+        }
+    }
+    scores
+        .into_iter()
+        .filter(|(_, score)| score.wr.total() >= min_games)
+        .map(|((account_id, hero_id), score)| {
+            let hero = heroes_info
+                .get_hero(hero_id)
+                .unwrap_or_else(|| Hero::unknown(hero_id));
+            score.into_item_build(member_names.get(&account_id), hero.name, items_info)
+        })
+        .sorted_by_key(|build| (build.player_name.clone(), std::cmp::Reverse(build.games)))
+        .collect()
+}
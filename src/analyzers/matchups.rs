@@ -0,0 +1,94 @@
+use crate::analyzers::analyzers_utils::countable_matches;
+use crate::analyzers::heroes::HeroName;
+use crate::analyzers::WinRatio;
+use crate::heroes_info::{Hero, HeroesInfo};
+use crate::match_stats::{Match, Side, Team};
+use crate::CONFIG;
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Number of enemy heroes listed as the hardest ones.
+const HARDEST_ENEMIES_COUNT: usize = 10;
+
+/// Winratio of heroes played by members against enemy heroes.
+/// member_vs_enemy - winratio of member hero against enemy hero.
+/// guild_vs_enemy - winratio of the guild in matches against enemy hero, whatever members played.
+/// hardest_enemies - enemy heroes with the lowest upper bound of guild winratio against them,
+///                   among ones met at least "min_matchup_games" times.
+#[derive(Serialize)]
+pub struct HeroMatchups {
+    member_vs_enemy: Vec<(HeroName, HeroName, WinRatio)>,
+    guild_vs_enemy: Vec<(HeroName, WinRatio)>,
+    hardest_enemies: Vec<(HeroName, WinRatio)>,
+}
+
+/// Ids of heroes played against the team, by other players and by members on the other side.
+fn enemy_heroes(match_: &Match, team: &Team, teams: &Vec<Team>) -> Vec<u64> {
+    let mut enemies = match_.get_other_heroes_ids(team, Side::Enemy);
+    for enemy_team in teams.iter().filter(|other| other.is_won() != team.is_won()) {
+        for account_id in enemy_team.get_players() {
+            if let Ok(hero_id) = match_.get_player_hero(account_id) {
+                enemies.push(hero_id);
+            }
+        }
+    }
+    enemies
+}
+
+/// Computes winratio of member heroes against enemy heroes. Abandoned matches are skipped.
+pub fn get_hero_matchups(matches: &Vec<Match>, heroes_info: &HeroesInfo) -> HeroMatchups {
+    let min_games = CONFIG.get_int("min_matchup_games").unwrap_or(5) as u32;
+    let hero_name = |hero_id: u64| -> HeroName {
+        heroes_info
+            .get_hero(hero_id)
+            .unwrap_or_else(|| Hero::unknown(hero_id))
+            .name
+    };
+    let mut member_vs_enemy: HashMap<(u64, u64), WinRatio> = HashMap::new();
+    let mut guild_vs_enemy: HashMap<u64, WinRatio> = HashMap::new();
+    for match_ in countable_matches(matches) {
+        let teams = skip_fail!(match_.get_teams());
+        for team in teams.iter() {
+            let enemies = enemy_heroes(match_, team, &teams);
+            for enemy_hero in enemies.iter().unique() {
+                guild_vs_enemy
+                    .entry(*enemy_hero)
+                    .or_default()
+                    .add_score(team.is_won());
+            }
+            for account_id in team.get_players() {
+                let member_hero = skip_fail!(match_.get_player_hero(account_id));
+                for enemy_hero in enemies.iter() {
+                    member_vs_enemy
+                        .entry((member_hero, *enemy_hero))
+                        .or_default()
+                        .add_score(team.is_won());
+                }
+            }
+        }
+    }
+    let hardest_enemies = guild_vs_enemy
+        .iter()
+        .filter(|(_, wr)| wr.total() >= min_games)
+        .sorted_by_key(|(_, wr)| OrderedFloat(wr.confidence_interval().1))
+        .take(HARDEST_ENEMIES_COUNT)
+        .map(|(enemy_hero, wr)| (hero_name(*enemy_hero), wr.clone()))
+        .collect();
+    HeroMatchups {
+        member_vs_enemy: member_vs_enemy
+            .into_iter()
+            .sorted_by_key(|(_, wr)| std::cmp::Reverse(wr.total()))
+            .map(|((member_hero, enemy_hero), wr)| {
+                (hero_name(member_hero), hero_name(enemy_hero), wr)
+            })
+            .collect(),
+        guild_vs_enemy: guild_vs_enemy
+            .into_iter()
+            .sorted_by_key(|(_, wr)| std::cmp::Reverse(wr.total()))
+            .map(|(enemy_hero, wr)| (hero_name(enemy_hero), wr))
+            .collect(),
+        hardest_enemies,
+    }
+}
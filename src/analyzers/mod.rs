@@ -1,7 +1,12 @@
 #[macro_use]
 pub mod analyzers_utils;
 pub mod heroes;
+pub mod items;
+pub mod matchups;
+pub mod overview;
+pub mod performance;
 pub mod players;
+pub mod positions;
 pub mod roles;
 mod winratio;
 
@@ -0,0 +1,156 @@
+use crate::analyzers::analyzers_utils::countable_matches;
+use crate::analyzers::WinRatio;
+use crate::match_stats::Match;
+use crate::CONFIG;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Region names by id, as in opendota region constants.
+const REGIONS: [(u64, &str); 22] = [
+    (1, "US West"),
+    (2, "US East"),
+    (3, "Europe West"),
+    (5, "SE Asia"),
+    (6, "Dubai"),
+    (7, "Australia"),
+    (8, "Stockholm"),
+    (9, "Austria"),
+    (10, "Brazil"),
+    (11, "South Africa"),
+    (12, "PW Telecom Shanghai"),
+    (13, "PW Unicom"),
+    (14, "Chile"),
+    (15, "Peru"),
+    (16, "India"),
+    (17, "PW Telecom Guangdong"),
+    (18, "PW Telecom Zhejiang"),
+    (19, "Japan"),
+    (20, "PW Telecom Wuhan"),
+    (25, "PW Unicom Tianjin"),
+    (37, "Taiwan"),
+    (38, "Argentina"),
+];
+
+/// Guild level stats of all guild matches.
+#[derive(Serialize)]
+pub struct GuildOverview {
+    overall: WinRatio,
+    average_duration: f64,
+    average_score: (f64, f64),
+    by_side: Vec<(String, WinRatio)>,
+    by_region: Vec<(String, WinRatio)>,
+    by_duration: Vec<(String, WinRatio)>,
+}
+
+fn region_name(region: u64) -> String {
+    match REGIONS.iter().find(|(id, _)| *id == region) {
+        Some((_, name)) => name.to_string(),
+        None => format!("Region #{}", region),
+    }
+}
+
+/// Bounds in minutes of match length buckets, set by "duration_buckets_minutes" config field.
+/// By default 20, 30, 40 and 50 minutes.
+fn duration_buckets() -> Vec<u64> {
+    CONFIG
+        .get::<Vec<u64>>("duration_buckets_minutes")
+        .unwrap_or(vec![20, 30, 40, 50])
+}
+
+/// Label of the bucket containing given duration, e.g. "<20", "20-30" or "50+".
+fn duration_bucket(buckets: &Vec<u64>, duration_secs: u64) -> String {
+    let minutes = duration_secs / 60;
+    let mut lower: Option<u64> = None;
+    for bound in buckets.iter() {
+        if minutes < *bound {
+            return match lower {
+                Some(lower) => format!("{}-{}", lower, bound),
+                None => format!("<{}", bound),
+            };
+        }
+        lower = Some(*bound);
+    }
+    match lower {
+        Some(lower) => format!("{}+", lower),
+        None => "all".to_string(),
+    }
+}
+
+fn average(values: &Vec<u64>) -> f64 {
+    if values.is_empty() {
+        return 0.;
+    }
+    let avg = values.iter().sum::<u64>() as f64 / values.len() as f64;
+    (avg * 10.).round() / 10.
+}
+
+/// Computes guild winratio by side, by region and by match length bucket. Matches in which
+/// members played against each other are counted for both teams. Abandoned matches are skipped.
+pub fn get_guild_overview(matches: &Vec<Match>) -> GuildOverview {
+    let buckets = duration_buckets();
+    let mut overall = WinRatio::default();
+    let mut by_side: HashMap<String, WinRatio> = HashMap::new();
+    let mut by_region: HashMap<String, WinRatio> = HashMap::new();
+    let mut by_duration: HashMap<u64, (String, WinRatio)> = HashMap::new();
+    let mut durations = vec![];
+    let mut team_scores = vec![];
+    let mut enemy_scores = vec![];
+    for match_ in countable_matches(matches) {
+        let teams = skip_fail!(match_.get_teams());
+        let match_stats = match_.get_match_stats();
+        if let Some(duration) = match_stats.get_duration() {
+            durations.push(duration);
+        }
+        for team in teams {
+            let is_won = team.is_won();
+            overall.add_score(is_won);
+            if let Some(is_radiant) = team.is_radiant() {
+                let side = if is_radiant { "Radiant" } else { "Dire" };
+                by_side
+                    .entry(side.to_string())
+                    .or_default()
+                    .add_score(is_won);
+            }
+            if let Some(region) = match_stats.get_region() {
+                by_region
+                    .entry(region_name(region))
+                    .or_default()
+                    .add_score(is_won);
+            }
+            if let Some(duration) = match_stats.get_duration() {
+                let bucket_order = buckets
+                    .iter()
+                    .filter(|bound| duration / 60 >= **bound)
+                    .count();
+                by_duration
+                    .entry(bucket_order as u64)
+                    .or_insert_with(|| (duration_bucket(&buckets, duration), WinRatio::default()))
+                    .1
+                    .add_score(is_won);
+            }
+            if let Ok((team_score, enemy_score)) = match_.get_score(&team) {
+                team_scores.push(team_score);
+                enemy_scores.push(enemy_score);
+            }
+        }
+    }
+    GuildOverview {
+        overall,
+        average_duration: average(&durations),
+        average_score: (average(&team_scores), average(&enemy_scores)),
+        by_side: by_side
+            .into_iter()
+            .sorted_by(|(a, _), (b, _)| b.cmp(a))
+            .collect(),
+        by_region: by_region
+            .into_iter()
+            .sorted_by_key(|(_, wr)| std::cmp::Reverse(wr.total()))
+            .collect(),
+        by_duration: by_duration
+            .into_iter()
+            .sorted_by_key(|(bucket_order, _)| *bucket_order)
+            .map(|(_, bucket)| bucket)
+            .collect(),
+    }
+}
@@ -0,0 +1,222 @@
+use crate::analyzers::analyzers_utils::countable_matches;
+use crate::analyzers::heroes::HeroName;
+use crate::heroes_info::{Hero, HeroesInfo};
+use crate::match_stats::{Match, MemberNames, PlayerName};
+use crate::types::AccountId;
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+pub type StatName = &'static str;
+
+/// Summary of single stat of a player over their games. Guild percentile tells which percent
+/// of members, or members who played the same hero, have lower mean of the stat. It is missing
+/// if no other member has the stat.
+#[derive(Serialize)]
+pub struct StatSummary {
+    games: u32,
+    mean: f64,
+    median: f64,
+    p25: f64,
+    p75: f64,
+    guild_percentile: Option<f64>,
+}
+
+/// Stats summaries of a player on certain hero.
+#[derive(Serialize)]
+pub struct HeroPerformance {
+    hero_name: HeroName,
+    games: u32,
+    stats: BTreeMap<StatName, StatSummary>,
+}
+
+/// Stats summaries of a player, overall and per hero.
+#[derive(Serialize)]
+pub struct PlayerPerformance {
+    player_name: PlayerName,
+    games: u32,
+    overall: BTreeMap<StatName, StatSummary>,
+    heroes: Vec<HeroPerformance>,
+}
+
+/// Values of each stat collected over the games. Stats missing in the match are skipped.
+#[derive(Default)]
+struct StatsSamples {
+    games: u32,
+    values: HashMap<StatName, Vec<f64>>,
+}
+
+impl StatsSamples {
+    fn add_game(&mut self, stats: Vec<(StatName, Option<f64>)>) {
+        self.games += 1;
+        for (stat_name, value) in stats {
+            if let Some(value) = value {
+                self.values.entry(stat_name).or_default().push(value);
+            }
+        }
+    }
+
+    fn mean(&self, stat_name: StatName) -> Option<f64> {
+        let values = self.values.get(stat_name)?;
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.).round() / 100.
+}
+
+/// Percentile of sorted values, linearly interpolated between closest ranks.
+fn percentile(sorted_values: &Vec<f64>, p: f64) -> f64 {
+    let rank = p / 100. * (sorted_values.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let weight = rank - lower as f64;
+    sorted_values[lower] * (1. - weight) + sorted_values[upper] * weight
+}
+
+/// Percent of the other samples with lower mean of the stat. None if there are no other
+/// samples to compare against.
+fn guild_percentile(
+    samples: &StatsSamples,
+    group: &Vec<&StatsSamples>,
+    stat_name: StatName,
+) -> Option<f64> {
+    let mean = samples.mean(stat_name)?;
+    let others: Vec<f64> = group
+        .iter()
+        .filter(|other| !std::ptr::eq(**other, samples))
+        .filter_map(|other| other.mean(stat_name))
+        .collect();
+    if others.is_empty() {
+        return None;
+    }
+    let lower = others
+        .iter()
+        .filter(|other_mean| **other_mean < mean)
+        .count();
+    Some(round(lower as f64 / others.len() as f64 * 100.))
+}
+
+/// Summarizes each stat of the samples, comparing them against the group.
+fn summarize(
+    samples: &StatsSamples,
+    group: &Vec<&StatsSamples>,
+) -> BTreeMap<StatName, StatSummary> {
+    samples
+        .values
+        .iter()
+        .map(|(stat_name, values)| {
+            let sorted_values: Vec<f64> = values
+                .iter()
+                .cloned()
+                .sorted_by_key(|value| OrderedFloat(*value))
+                .collect();
+            let summary = StatSummary {
+                games: values.len() as u32,
+                mean: round(samples.mean(*stat_name).unwrap_or_default()),
+                median: round(percentile(&sorted_values, 50.)),
+                p25: round(percentile(&sorted_values, 25.)),
+                p75: round(percentile(&sorted_values, 75.)),
+                guild_percentile: guild_percentile(samples, group, *stat_name),
+            };
+            (*stat_name, summary)
+        })
+        .collect()
+}
+
+/// Computes performance of each member over all PlayerStats numeric fields, overall and per hero.
+/// Abandoned matches are skipped.
+pub fn get_players_performance(
+    matches: &Vec<Match>,
+    heroes_info: &HeroesInfo,
+    member_names: &MemberNames,
+) -> Vec<PlayerPerformance> {
+    let mut overall: HashMap<AccountId, StatsSamples> = HashMap::new();
+    let mut per_hero: HashMap<(AccountId, u64), StatsSamples> = HashMap::new();
+    for match_ in countable_matches(matches) {
+        let teams = skip_fail!(match_.get_teams());
+        for account_id in teams.iter().flat_map(|team| team.get_players().clone()) {
+            let player_stats = skip_fail!(match_.get_player_stats(&account_id));
+            let hero_id = skip_fail!(match_.get_player_hero(&account_id));
+            overall
+                .entry(account_id)
+                .or_default()
+                .add_game(player_stats.get_numeric_stats());
+            per_hero
+                .entry((account_id, hero_id))
+                .or_default()
+                .add_game(player_stats.get_numeric_stats());
+        }
+    }
+    let overall_group: Vec<&StatsSamples> = overall.values().collect();
+    let mut hero_groups: HashMap<u64, Vec<&StatsSamples>> = HashMap::new();
+    for ((_, hero_id), samples) in per_hero.iter() {
+        hero_groups.entry(*hero_id).or_default().push(samples);
+    }
+    overall
+        .iter()
+        .map(|(account_id, samples)| {
+            let heroes = per_hero
+                .iter()
+                .filter(|((player, _), _)| player == account_id)
+                .map(|((_, hero_id), hero_samples)| HeroPerformance {
+                    hero_name: heroes_info
+                        .get_hero(*hero_id)
+                        .unwrap_or_else(|| Hero::unknown(*hero_id))
+                        .name,
+                    games: hero_samples.games,
+                    stats: summarize(hero_samples, &hero_groups[hero_id]),
+                })
+                .sorted_by_key(|hero| std::cmp::Reverse(hero.games))
+                .collect();
+            PlayerPerformance {
+                player_name: member_names.get(account_id),
+                games: samples.games,
+                overall: summarize(samples, &overall_group),
+                heroes,
+            }
+        })
+        .sorted_by_key(|performance| performance.player_name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(kills: &[f64]) -> StatsSamples {
+        let mut samples = StatsSamples::default();
+        for value in kills {
+            samples.add_game(vec![("kills", Some(*value))]);
+        }
+        samples
+    }
+
+    #[test]
+    fn percentile_is_missing_without_other_members() {
+        let only = samples(&[5.]);
+        let group = vec![&only];
+        assert_eq!(guild_percentile(&only, &group, "kills"), None);
+    }
+
+    #[test]
+    fn percentile_ignores_members_without_the_stat() {
+        let member = samples(&[5.]);
+        let without_stat = StatsSamples::default();
+        let group = vec![&member, &without_stat];
+        assert_eq!(guild_percentile(&member, &group, "kills"), None);
+    }
+
+    #[test]
+    fn percentile_counts_other_members_with_lower_mean() {
+        let best = samples(&[9., 11.]);
+        let middle = samples(&[5.]);
+        let worst = samples(&[1.]);
+        let group = vec![&best, &middle, &worst];
+        assert_eq!(guild_percentile(&best, &group, "kills"), Some(100.));
+        assert_eq!(guild_percentile(&middle, &group, "kills"), Some(50.));
+        assert_eq!(guild_percentile(&worst, &group, "kills"), Some(0.));
+    }
+}
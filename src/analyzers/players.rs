@@ -1,21 +1,68 @@
+use crate::analyzers::analyzers_utils::countable_matches;
 use crate::analyzers::WinRatio;
-use crate::match_stats::Match;
-use crate::match_stats::PlayerName;
+use crate::match_stats::{Match, MemberNames, PlayerName};
+use crate::types::AccountId;
 use itertools::Itertools;
 use std::collections::HashMap;
 
-/// Computes winratio for each Player setup.
-pub fn get_players_wr(matches: &Vec<Match>) -> Vec<(Vec<PlayerName>, WinRatio)> {
-    let mut players_score: HashMap<Vec<PlayerName>, WinRatio> = HashMap::new();
-    for match_ in matches {
-        let mut team = skip_fail!(match_.get_team());
-        team.sort();
-        let is_won = skip_fail!(match_.is_won());
-        for i in 1..=(team.len()) {
-            for subset in team.clone().into_iter().combinations(i) {
-                players_score.entry(subset).or_default().add_score(is_won);
+/// Computes winratio for each Player setup. Only teammates are combined into setups.
+/// Abandoned matches are skipped.
+pub fn get_players_wr(
+    matches: &Vec<Match>,
+    member_names: &MemberNames,
+) -> Vec<(Vec<PlayerName>, WinRatio)> {
+    let mut players_score: HashMap<Vec<AccountId>, WinRatio> = HashMap::new();
+    for match_ in countable_matches(matches) {
+        for team in skip_fail!(match_.get_teams()) {
+            let mut players = team.get_players().clone();
+            players.sort();
+            for i in 1..=(players.len()) {
+                for subset in players.clone().into_iter().combinations(i) {
+                    players_score
+                        .entry(subset)
+                        .or_default()
+                        .add_score(team.is_won());
+                }
             }
         }
     }
-    players_score.into_iter().collect()
+    players_score
+        .into_iter()
+        .map(|(players, wr)| (players.iter().map(|p| member_names.get(p)).collect(), wr))
+        .collect()
+}
+
+/// Computes record of each member against each other member, from matches in which they
+/// played on opposite sides. Abandoned matches are skipped.
+pub fn get_members_versus(
+    matches: &Vec<Match>,
+    member_names: &MemberNames,
+) -> Vec<(PlayerName, PlayerName, WinRatio)> {
+    let mut versus_score: HashMap<(AccountId, AccountId), WinRatio> = HashMap::new();
+    for match_ in countable_matches(matches) {
+        let teams = skip_fail!(match_.get_teams());
+        if teams.len() != 2 {
+            continue;
+        }
+        for player in teams[0].get_players() {
+            for opponent in teams[1].get_players() {
+                let is_won = teams[0].is_won();
+                versus_score
+                    .entry((*player, *opponent))
+                    .or_default()
+                    .add_score(is_won);
+                versus_score
+                    .entry((*opponent, *player))
+                    .or_default()
+                    .add_score(!is_won);
+            }
+        }
+    }
+    versus_score
+        .into_iter()
+        .map(|((player, opponent), wr)| {
+            (member_names.get(&player), member_names.get(&opponent), wr)
+        })
+        .sorted_by_key(|(player, opponent, _)| (player.clone(), opponent.clone()))
+        .collect()
 }
@@ -0,0 +1,99 @@
+use crate::analyzers::analyzers_utils::countable_matches;
+use crate::analyzers::roles::{RoleName, Roles, RolesWr};
+use crate::analyzers::WinRatio;
+use crate::match_stats::{Match, PlayerStats};
+use crate::types::AccountId;
+use crate::CONFIG;
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Lane roles as set by opendota parser.
+const SAFE_LANE: u64 = 1;
+const MID_LANE: u64 = 2;
+const OFF_LANE: u64 = 3;
+const JUNGLE: u64 = 4;
+
+/// Minimal gold per minute of a core player, set by "core_min_gpm" config field. Used only if
+/// there is no other member in the lane to compare farm with.
+fn core_min_gpm() -> u64 {
+    CONFIG.get_int("core_min_gpm").unwrap_or(400) as u64
+}
+
+/// Farm of the player, compared by gold per minute and then by last hits.
+fn farm(player: &PlayerStats) -> Option<(u64, u64)> {
+    Some((
+        player.get_gold_per_min()?,
+        player.get_last_hits().unwrap_or_default(),
+    ))
+}
+
+/// Checks whether player was the core of the lane. Player is the core if they farmed more than
+/// other members in the lane, or if they were alone and farmed at least "core_min_gpm".
+fn is_core(player: &PlayerStats, lane_mates: &Vec<&PlayerStats>) -> Option<bool> {
+    let player_farm = farm(player)?;
+    let mates_farm: Vec<(u64, u64)> = lane_mates.iter().filter_map(|mate| farm(mate)).collect();
+    if mates_farm.is_empty() {
+        return Some(player_farm.0 >= core_min_gpm());
+    }
+    Some(mates_farm.iter().all(|mate_farm| player_farm > *mate_farm))
+}
+
+/// Infers position (1-5) actually played by the member, based on lane role, roaming and farm.
+/// Lane mates are members of the same team with the same lane role.
+fn infer_position(player: &PlayerStats, lane_mates: &Vec<&PlayerStats>) -> Option<u64> {
+    if player.get_is_roaming() == Some(true) {
+        return Some(4);
+    }
+    match player.get_lane_role()? {
+        MID_LANE => Some(2),
+        SAFE_LANE => Some(if is_core(player, lane_mates)? { 1 } else { 5 }),
+        OFF_LANE => Some(if is_core(player, lane_mates)? { 3 } else { 4 }),
+        JUNGLE => Some(if is_core(player, lane_mates)? { 1 } else { 4 }),
+        _ => None,
+    }
+}
+
+/// Infers positions of the team members. Members with unknown position are skipped.
+pub fn infer_positions(match_: &Match, team: &Vec<AccountId>) -> Vec<(AccountId, RoleName)> {
+    let players: Vec<(AccountId, &PlayerStats)> = team
+        .iter()
+        .filter_map(|account_id| Some((*account_id, match_.get_player_stats(account_id).ok()?)))
+        .collect();
+    players
+        .iter()
+        .filter_map(|(account_id, player)| {
+            let lane_mates: Vec<&PlayerStats> = players
+                .iter()
+                .filter(|(mate_id, mate)| {
+                    mate_id != account_id
+                        && mate.get_is_roaming() != Some(true)
+                        && mate.get_lane_role() == player.get_lane_role()
+                })
+                .map(|(_, mate)| *mate)
+                .collect();
+            let position = infer_position(player, &lane_mates)?;
+            Some((*account_id, format!("Pos {}", position)))
+        })
+        .sorted()
+        .collect()
+}
+
+/// Computes winratio for each Player-Position setup, with positions inferred for each match.
+/// Only teammates are combined into setups. Abandoned matches are skipped.
+pub fn get_positions_wr(matches: &Vec<Match>) -> RolesWr {
+    let mut positions_score: HashMap<Roles, WinRatio> = HashMap::new();
+    for match_ in countable_matches(matches) {
+        for team in skip_fail!(match_.get_teams()) {
+            let positions = infer_positions(match_, team.get_players());
+            for i in 1..=(positions.len()) {
+                for subset in positions.clone().into_iter().combinations(i) {
+                    positions_score
+                        .entry(subset)
+                        .or_default()
+                        .add_score(team.is_won());
+                }
+            }
+        }
+    }
+    positions_score.into_iter().collect()
+}
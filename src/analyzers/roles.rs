@@ -1,20 +1,30 @@
 use crate::analyzers::analyzers_utils::*;
 use crate::analyzers::WinRatio;
 use crate::heroes_info::{Hero, HeroesInfo};
-use crate::match_stats::Match;
-use crate::match_stats::PlayerName;
+use crate::match_stats::{Match, MemberNames, PlayerName};
+use crate::types::AccountId;
 use crate::CONFIG;
 use serde::Serialize;
 use std::collections::HashMap;
 
 pub type RoleName = String;
-pub type Roles = Vec<(PlayerName, RoleName)>;
+pub type Roles = Vec<(AccountId, RoleName)>;
 pub type RolesWr = Vec<(Roles, WinRatio)>;
+pub type NamedRoles = Vec<(PlayerName, RoleName)>;
+pub type NamedRolesWr = Vec<(NamedRoles, WinRatio)>;
 pub type RolesSynergyResult = f64;
 
+/// Replaces account ids of players in setup with their names.
+fn name_roles(roles: &Roles, member_names: &MemberNames) -> NamedRoles {
+    roles
+        .iter()
+        .map(|(account_id, role)| (member_names.get(account_id), role.clone()))
+        .collect()
+}
+
 /// As every hero can have several roles, this function generates subsets of those roles for a team.
 /// e.g. P1: [A, B], P2: [B, C] -> [(P1-A, P2-B), (P1-A, P2-C), (P1-B, P2-B), (P1-B, P2-C)].
-fn get_role_subsets(team_setup: Vec<(PlayerName, Hero)>) -> Vec<Roles> {
+fn get_role_subsets(team_setup: Vec<(AccountId, Hero)>) -> Vec<Roles> {
     let mut role_subsets = vec![Vec::new()];
     for (player, hero) in team_setup.iter() {
         role_subsets = [
@@ -35,27 +45,23 @@ fn get_role_subsets(team_setup: Vec<(PlayerName, Hero)>) -> Vec<Roles> {
     role_subsets
 }
 
-/// Computes winratio for each Player-Role setup.
-pub fn get_roles_wr(matches: &Vec<Match>) -> RolesWr {
-    let heroes_info_filename = CONFIG.get_str("heroes_info_filename").unwrap().to_string();
-    let heroes_info = match HeroesInfo::init(heroes_info_filename) {
-        Ok(val) => val,
-        Err(e) => {
-            warn!("Can't load heroes_info from heroes.json: {}", e);
-            return vec![];
-        }
-    };
+/// Computes winratio for each Player-Role setup. Only teammates are combined into setups.
+/// Abandoned matches are skipped.
+pub fn get_roles_wr(matches: &Vec<Match>, heroes_info: &HeroesInfo) -> RolesWr {
     let mut roles_score: HashMap<Roles, WinRatio> = HashMap::new();
-    for match_ in matches {
-        let team = skip_fail!(match_.get_team());
-        let team_setup = get_heroes(&heroes_info, match_, team);
-        let role_subsets = get_role_subsets(team_setup);
-        let is_won = skip_fail!(match_.is_won());
-        for subset in role_subsets {
-            if subset.is_empty() {
-                continue;
+    for match_ in countable_matches(matches) {
+        for team in skip_fail!(match_.get_teams()) {
+            let team_setup = get_heroes(heroes_info, match_, team.get_players().clone());
+            let role_subsets = get_role_subsets(team_setup);
+            for subset in role_subsets {
+                if subset.is_empty() {
+                    continue;
+                }
+                roles_score
+                    .entry(subset)
+                    .or_default()
+                    .add_score(team.is_won());
             }
-            roles_score.entry(subset).or_default().add_score(is_won);
         }
     }
     roles_score.into_iter().collect()
@@ -63,9 +69,12 @@ pub fn get_roles_wr(matches: &Vec<Match>) -> RolesWr {
 
 /// Computes synergy factor for each Player-Role setup. Synergy is equal to setup winratio
 /// divided by average of each player in the setup (player-role) individual winratio.
-pub fn get_roles_synergies(roles_wr: &RolesWr) -> Vec<(Roles, RolesSynergyResult)> {
+pub fn get_roles_synergies(
+    roles_wr: &RolesWr,
+    member_names: &MemberNames,
+) -> Vec<(NamedRoles, RolesSynergyResult)> {
     let single_wr = roles_wr.iter().filter(|(r, _)| r.len() == 1).fold(
-        HashMap::<(String, String), WinRatio>::new(),
+        HashMap::<(AccountId, RoleName), WinRatio>::new(),
         |mut s, (roles, wr)| {
             s.insert(roles[0].clone(), wr.clone());
             s
@@ -80,14 +89,18 @@ pub fn get_roles_synergies(roles_wr: &RolesWr) -> Vec<(Roles, RolesSynergyResult
             }
             let mut avg_solo_wr = 0.;
             for role in roles.iter() {
-                avg_solo_wr += (single_wr[role].clone() - team_wr.clone())
-                    .expect("Single wr does no contain some team wr.")
-                    .as_percent();
+                let solo_wr = (single_wr[role].clone() - team_wr.clone())
+                    .expect("Single wr does no contain some team wr.");
+                if solo_wr.total() == 0 {
+                    // Player never played the role outside of the setup.
+                    return None;
+                }
+                avg_solo_wr += solo_wr.as_percent();
             }
             avg_solo_wr = avg_solo_wr / roles.len() as f64;
             let synergy = team_wr.as_percent() / avg_solo_wr;
             let synergy = (synergy * 1000.).round() / 1000.;
-            Some((roles.clone(), synergy))
+            Some((name_roles(roles, member_names), synergy))
         })
         .collect()
 }
@@ -108,17 +121,17 @@ pub struct RolesRecords {
 
 impl RolesRecords {
     /// Constructs RolesRecords using Player-Role setups winratio.
-    pub fn extract_records(roles_wr: &RolesWr) -> Self {
+    pub fn extract_records(roles_wr: &RolesWr, member_names: &MemberNames) -> Self {
         let relevant_total_games = CONFIG.get_int("min_roles_wr_games").unwrap() as u32;
         let roles_wr = roles_wr
             .iter()
             .filter(|(_, wr)| wr.total() > relevant_total_games)
             .map(|t| t.clone())
             .collect();
-        let best_single = RolesRecords::extract_best_single(&roles_wr);
-        let top3_carry_sup = RolesRecords::extract_top3_carry_sup(&roles_wr);
-        let best_fight_crew = RolesRecords::extract_best_fight_crew(&roles_wr);
-        let best_nuking_squad = RolesRecords::extract_best_nuking_squad(&roles_wr);
+        let best_single = RolesRecords::extract_best_single(&roles_wr, member_names);
+        let top3_carry_sup = RolesRecords::extract_top3_carry_sup(&roles_wr, member_names);
+        let best_fight_crew = RolesRecords::extract_best_fight_crew(&roles_wr, member_names);
+        let best_nuking_squad = RolesRecords::extract_best_nuking_squad(&roles_wr, member_names);
         RolesRecords {
             best_single,
             top3_carry_sup,
@@ -128,12 +141,15 @@ impl RolesRecords {
     }
 
     /// Calculates best_single field.
-    fn extract_best_single(roles_wr: &RolesWr) -> Vec<(PlayerName, RoleName, WinRatio)> {
+    fn extract_best_single(
+        roles_wr: &RolesWr,
+        member_names: &MemberNames,
+    ) -> Vec<(PlayerName, RoleName, WinRatio)> {
         roles_wr
             .iter()
             .filter(|(roles, _)| roles.len() == 1)
             .fold(
-                HashMap::<&RoleName, Vec<(&PlayerName, &WinRatio)>>::new(),
+                HashMap::<&RoleName, Vec<(&AccountId, &WinRatio)>>::new(),
                 |mut s, (roles, wr)| {
                     s.entry(&roles[0].1)
                         .or_insert(vec![])
@@ -144,7 +160,7 @@ impl RolesRecords {
             .into_iter()
             .map(|(role, players_wr)| {
                 let (player, wr) = match players_wr.into_iter().max_by_key(|p| p.1) {
-                    Some((player_max, wr_max)) => (player_max.clone(), wr_max.clone()),
+                    Some((player_max, wr_max)) => (member_names.get(player_max), wr_max.clone()),
                     None => Default::default(),
                 };
                 (role.clone(), player.clone(), wr.clone())
@@ -153,7 +169,10 @@ impl RolesRecords {
     }
 
     /// Calculates top3_carry_sup field.
-    fn extract_top3_carry_sup(roles_wr: &RolesWr) -> [(PlayerName, PlayerName, WinRatio); 3] {
+    fn extract_top3_carry_sup(
+        roles_wr: &RolesWr,
+        member_names: &MemberNames,
+    ) -> [(PlayerName, PlayerName, WinRatio); 3] {
         let mut carry_sup: Vec<(PlayerName, PlayerName, WinRatio)> = roles_wr
             .iter()
             .filter(|(roles, _)| roles.len() == 2)
@@ -162,7 +181,13 @@ impl RolesRecords {
                 r.sort();
                 r == vec!["Carry", "Support"]
             })
-            .map(|(roles, wr)| (roles[0].0.clone(), roles[1].0.clone(), wr.clone()))
+            .map(|(roles, wr)| {
+                (
+                    member_names.get(&roles[0].0),
+                    member_names.get(&roles[1].0),
+                    wr.clone(),
+                )
+            })
             .collect();
         carry_sup.sort_by_key(|t| t.2.clone());
         carry_sup.reverse();
@@ -176,6 +201,7 @@ impl RolesRecords {
     /// Calculates best_fight_crew field.
     fn extract_best_fight_crew(
         roles_wr: &RolesWr,
+        member_names: &MemberNames,
     ) -> (PlayerName, PlayerName, PlayerName, WinRatio) {
         let best_fight_crew = roles_wr
             .iter()
@@ -195,9 +221,9 @@ impl RolesRecords {
             .max_by_key(|(_, wr)| wr);
         match best_fight_crew {
             Some((roles, wr)) => (
-                roles[0].0.clone(),
-                roles[1].0.clone(),
-                roles[2].0.clone(),
+                member_names.get(&roles[0].0),
+                member_names.get(&roles[1].0),
+                member_names.get(&roles[2].0),
                 wr.clone(),
             ),
             None => Default::default(),
@@ -205,7 +231,10 @@ impl RolesRecords {
     }
 
     /// Calculates best_nuking field.
-    fn extract_best_nuking_squad(roles_wr: &RolesWr) -> (PlayerName, PlayerName, WinRatio) {
+    fn extract_best_nuking_squad(
+        roles_wr: &RolesWr,
+        member_names: &MemberNames,
+    ) -> (PlayerName, PlayerName, WinRatio) {
         let squad = roles_wr
             .iter()
             .filter(|(roles, _)| roles.len() == 2)
@@ -215,21 +244,26 @@ impl RolesRecords {
             })
             .max_by_key(|(_, wr)| wr);
         match squad {
-            Some((roles, wr)) => (roles[0].0.clone(), roles[1].0.clone(), wr.clone()),
+            Some((roles, wr)) => (
+                member_names.get(&roles[0].0),
+                member_names.get(&roles[1].0),
+                wr.clone(),
+            ),
             None => Default::default(),
         }
     }
 }
 
-pub fn get_roles_records(roles_wr: &RolesWr) -> RolesRecords {
-    RolesRecords::extract_records(roles_wr)
+pub fn get_roles_records(roles_wr: &RolesWr, member_names: &MemberNames) -> RolesRecords {
+    RolesRecords::extract_records(roles_wr, member_names)
 }
 
 /// Drops setups with not enough total games. Used after calculating other stats.
-pub fn compress_roles_wr(roles_wr: RolesWr) -> RolesWr {
+pub fn compress_roles_wr(roles_wr: RolesWr, member_names: &MemberNames) -> NamedRolesWr {
     let relevant_total_games = CONFIG.get_int("min_roles_wr_games").unwrap() as u32;
     roles_wr
         .into_iter()
         .filter(|(_, wr)| wr.total() >= relevant_total_games)
+        .map(|(roles, wr)| (name_roles(&roles, member_names), wr))
         .collect()
 }
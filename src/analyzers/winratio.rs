@@ -1,10 +1,52 @@
+use crate::CONFIG;
+use ordered_float::OrderedFloat;
+use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::ops::Add;
 use std::ops::Sub;
 
+/// Estimate of winratio used to rank setups, set by "winratio_ranking" config field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RankingEstimate {
+    /// Plain wins / total.
+    Raw,
+    /// Lower bound of Wilson score interval.
+    Wilson,
+    /// Winratio shrunk towards prior winratio by prior games.
+    Bayesian,
+}
+
+/// Parameters of winratio estimates, read once from config.
+/// "winratio_confidence_z" - z-score of the confidence interval, 1.96 (95%) by default.
+/// "winratio_prior_mean" and "winratio_prior_games" - prior of Bayesian estimate, by default
+/// as if each setup had 10 more games with 50% winratio.
+struct EstimateParams {
+    ranking: RankingEstimate,
+    z: f64,
+    prior_mean: f64,
+    prior_games: f64,
+}
+
+lazy_static! {
+    static ref ESTIMATE_PARAMS: EstimateParams = EstimateParams {
+        ranking: match CONFIG
+            .get_str("winratio_ranking")
+            .unwrap_or("wilson".to_string())
+            .as_str()
+        {
+            "raw" => RankingEstimate::Raw,
+            "bayesian" => RankingEstimate::Bayesian,
+            _ => RankingEstimate::Wilson,
+        },
+        z: CONFIG.get_float("winratio_confidence_z").unwrap_or(1.96),
+        prior_mean: CONFIG.get_float("winratio_prior_mean").unwrap_or(0.5),
+        prior_games: CONFIG.get_float("winratio_prior_games").unwrap_or(10.),
+    };
+}
+
 /// Struct used by analyzers to keep winratio score of certain setups.
-#[derive(Debug, Default, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Eq, Clone, Deserialize)]
 pub struct WinRatio {
     pub wins: u32,
     pub looses: u32,
@@ -33,6 +75,10 @@ impl Sub for WinRatio {
     }
 }
 
+fn round(value: f64) -> f64 {
+    (value * 1000.).round() / 1000.
+}
+
 impl WinRatio {
     pub fn add_score(&mut self, win: bool) {
         if win {
@@ -42,36 +88,161 @@ impl WinRatio {
         }
     }
 
+    /// Raw winratio, 0 if there are no games.
     pub fn as_percent(&self) -> f64 {
-        if self.looses == 0 {
-            1.0
+        if self.total() == 0 {
+            0.
         } else {
-            self.wins as f64 / (self.wins + self.looses) as f64
+            self.wins as f64 / self.total() as f64
         }
     }
 
     pub fn total(&self) -> u32 {
         self.wins + self.looses
     }
+
+    /// Wilson score interval of winratio for given z-score. (0, 1) if there are no games.
+    pub fn wilson_interval(&self, z: f64) -> (f64, f64) {
+        if self.total() == 0 {
+            return (0., 1.);
+        }
+        let n = self.total() as f64;
+        let p = self.as_percent();
+        let z2 = z * z;
+        let center = p + z2 / (2. * n);
+        let margin = z * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt();
+        let denominator = 1. + z2 / n;
+        (
+            ((center - margin) / denominator).max(0.),
+            ((center + margin) / denominator).min(1.),
+        )
+    }
+
+    /// Lower bound of Wilson score interval for given z-score.
+    pub fn wilson_lower_bound(&self, z: f64) -> f64 {
+        self.wilson_interval(z).0
+    }
+
+    /// Winratio shrunk towards prior mean, as if setup had additional prior games.
+    pub fn bayesian(&self, prior_mean: f64, prior_games: f64) -> f64 {
+        (self.wins as f64 + prior_mean * prior_games) / (self.total() as f64 + prior_games)
+    }
+
+    /// Confidence interval with z-score set by "winratio_confidence_z".
+    pub fn confidence_interval(&self) -> (f64, f64) {
+        self.wilson_interval(ESTIMATE_PARAMS.z)
+    }
+
+    /// Conservative estimate of winratio set by "winratio_ranking", used to rank setups.
+    pub fn score(&self) -> f64 {
+        let params = &*ESTIMATE_PARAMS;
+        match params.ranking {
+            RankingEstimate::Raw => self.as_percent(),
+            RankingEstimate::Wilson => self.wilson_lower_bound(params.z),
+            RankingEstimate::Bayesian => self.bayesian(params.prior_mean, params.prior_games),
+        }
+    }
 }
 
+/// Serialized with the ranking score and confidence interval next to the raw counts.
+impl Serialize for WinRatio {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct WinRatioOutput {
+            wins: u32,
+            looses: u32,
+            score: f64,
+            confidence_interval: (f64, f64),
+        }
+        let (lower, upper) = self.confidence_interval();
+        WinRatioOutput {
+            wins: self.wins,
+            looses: self.looses,
+            score: round(self.score()),
+            confidence_interval: (round(lower), round(upper)),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Winratios are ordered by the ranking score, then by total games.
 impl Ord for WinRatio {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        match OrderedFloat(self.score()).cmp(&OrderedFloat(other.score())) {
+            Ordering::Equal => self.total().cmp(&other.total()),
+            ord => ord,
+        }
     }
 }
 
 impl PartialOrd for WinRatio {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (other.looses * self.wins).cmp(&(other.wins * self.looses)) {
-            Ordering::Equal => Some(self.total().cmp(&other.total())),
-            ord => Some(ord),
-        }
+        Some(self.cmp(other))
     }
 }
 
 impl PartialEq for WinRatio {
     fn eq(&self, other: &Self) -> bool {
-        (self.looses * other.wins) == (self.wins * other.looses)
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Z: f64 = 1.96;
+
+    fn wr(wins: u32, looses: u32) -> WinRatio {
+        WinRatio { wins, looses }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn no_games() {
+        let wr = wr(0, 0);
+        assert_eq!(wr.as_percent(), 0.);
+        assert_eq!(wr.wilson_interval(Z), (0., 1.));
+        assert_close(wr.bayesian(0.5, 10.), 0.5);
+    }
+
+    #[test]
+    fn single_win_ranks_below_sixty_forty() {
+        let single_win = wr(1, 0);
+        let sixty_forty = wr(60, 40);
+        assert!(single_win.as_percent() > sixty_forty.as_percent());
+        assert!(single_win.wilson_lower_bound(Z) < sixty_forty.wilson_lower_bound(Z));
+        assert!(single_win.bayesian(0.5, 10.) < sixty_forty.bayesian(0.5, 10.));
+    }
+
+    #[test]
+    fn interval_bounds() {
+        let (lower, upper) = wr(1, 0).wilson_interval(Z);
+        assert_close(lower, 0.2065);
+        assert_close(upper, 1.);
+        let (lower, upper) = wr(60, 40).wilson_interval(Z);
+        assert_close(lower, 0.5020);
+        assert_close(upper, 0.6906);
+        let (lower, upper) = wr(0, 5).wilson_interval(Z);
+        assert_close(lower, 0.);
+        assert!(upper > 0. && upper < 1.);
+    }
+
+    #[test]
+    fn interval_contains_raw_winratio() {
+        for (wins, looses) in [(0, 1), (1, 1), (3, 7), (9, 1), (50, 50)].iter() {
+            let wr = wr(*wins, *looses);
+            let (lower, upper) = wr.wilson_interval(Z);
+            assert!(0. <= lower && lower <= wr.as_percent());
+            assert!(wr.as_percent() <= upper && upper <= 1.);
+        }
     }
 }
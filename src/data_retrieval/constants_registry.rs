@@ -0,0 +1,68 @@
+use crate::data_retrieval::constants_source::fetch_constants;
+use crate::storage::Storage;
+use crate::BoxError;
+use crate::CONFIG;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Reads constants of some kind from json, tagged with their stored version.
+pub type ConstantsParser<T> = fn(&serde_json::Value, i64) -> Result<T, BoxError>;
+
+/// Newest version of game constants of one kind (e.g. heroes), shared by all analyses.
+/// Constants are stored in the db under their kind and loaded on first use.
+pub struct ConstantsRegistry<T> {
+    kind: &'static str,
+    parse: ConstantsParser<T>,
+    current: RwLock<Option<Arc<T>>>,
+}
+
+impl<T> ConstantsRegistry<T> {
+    pub fn new(kind: &'static str, parse: ConstantsParser<T>) -> Self {
+        Self {
+            kind,
+            parse,
+            current: RwLock::new(None),
+        }
+    }
+
+    /// Source of constants, set by "{kind}_constants_source" config field. Either url
+    /// or path to the local file. Opendota constants endpoint by default.
+    fn source(&self) -> String {
+        CONFIG
+            .get_str(&format!("{}_constants_source", self.kind))
+            .unwrap_or(format!(
+                "https://api.opendota.com/api/constants/{}",
+                self.kind
+            ))
+    }
+
+    /// Reads constants from their source and stores them as a new version if they changed.
+    /// Registry is updated with the newest version. Constants are parsed before they are
+    /// stored, so invalid ones never become the newest version.
+    pub async fn refresh(&self, storage: &Storage) -> Result<Arc<T>, BoxError> {
+        let raw_constants = fetch_constants(&self.source()).await?;
+        if let Err(e) = (self.parse)(&raw_constants, 0) {
+            return Err(format!("Invalid {} constants: {}", self.kind, e).into());
+        }
+        let stored = storage.store_constants(self.kind, &raw_constants).await?;
+        let constants = Arc::new((self.parse)(&raw_constants, stored.version)?);
+        *self.current.write().await = Some(constants.clone());
+        Ok(constants)
+    }
+
+    /// Returns shared constants. On first use they are loaded from the db, or from their
+    /// source if none are stored yet.
+    pub async fn get(&self, storage: &Storage) -> Result<Arc<T>, BoxError> {
+        if let Some(constants) = self.current.read().await.as_ref() {
+            return Ok(constants.clone());
+        }
+        let stored = match storage.get_constants(self.kind).await? {
+            Some(stored) => stored,
+            None => return self.refresh(storage).await,
+        };
+        let raw_constants: serde_json::Value = serde_json::from_str(&stored.data)?;
+        let constants = Arc::new((self.parse)(&raw_constants, stored.version)?);
+        *self.current.write().await = Some(constants.clone());
+        Ok(constants)
+    }
+}
@@ -0,0 +1,18 @@
+use crate::data_retrieval::fetch_error::FetchError;
+use crate::data_retrieval::http_transport::HttpTransport;
+use crate::BoxError;
+use tokio::fs;
+
+/// Reads constants json from source, which is either http url (e.g. opendota constants
+/// endpoint) or path to the local file.
+pub async fn fetch_constants(source: &str) -> Result<serde_json::Value, BoxError> {
+    info!("Fetching constants from: {}", source);
+    let body = if source.starts_with("http://") || source.starts_with("https://") {
+        let response = HttpTransport::from_config().get(source).await?;
+        FetchError::check_response(&response)?;
+        response.body
+    } else {
+        fs::read_to_string(source).await?
+    };
+    Ok(serde_json::from_str(&body)?)
+}
@@ -1,47 +1,94 @@
-use crate::data_retrieval::opendota_client::OpenDotaClient;
-use crate::storage::Storage;
-use crate::types::{GuildId, MatchId};
+use crate::data_retrieval::fetch_error::FetchError;
+use crate::data_retrieval::match_data_provider::{FallbackProvider, MatchDataProvider};
+use crate::opendota_models::{MatchDetails, MatchSummary, PlayerInfo};
+use crate::storage::{
+    guild_info_storage::GuildInfo,
+    guild_membership_storage::{derive_tenures, MemberTenure},
+    player_groups_storage::is_group_id,
+    sync_state_storage::MemberSyncState,
+    Storage,
+};
+use crate::types::{GuildId, MatchId, PlayerId};
 use crate::BoxError;
 use crate::CONFIG;
-use serde_json;
-use std::collections::HashSet;
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-/// Retrieves guild matches data either from opendota or from storage.
+/// Retrieves guild matches data either from match data providers or from storage.
 pub struct DataRetriever {
     storage: Arc<Storage>,
-    od_client: OpenDotaClient,
+    provider: Box<dyn MatchDataProvider>,
 }
 
-/// Retrieval result.
+/// Retrieval result. Members include players who left the guild.
 pub struct GuildRawData {
     pub guild_id: GuildId,
-    pub members: Vec<serde_json::Value>,
-    pub members_matches: Vec<serde_json::Value>,
+    pub members_ids: Vec<PlayerId>,
+    pub tenures: Vec<MemberTenure>,
+    pub members: Vec<PlayerInfo>,
+    pub members_matches: Vec<MatchDetails>,
 }
 
 impl DataRetriever {
     pub fn new(storage: Arc<Storage>) -> DataRetriever {
-        DataRetriever {
-            storage,
-            od_client: OpenDotaClient::new(),
-        }
+        DataRetriever::with_provider(storage, Box::new(FallbackProvider::from_config()))
+    }
+
+    pub fn with_provider(
+        storage: Arc<Storage>,
+        provider: Box<dyn MatchDataProvider>,
+    ) -> DataRetriever {
+        DataRetriever { storage, provider }
     }
 
     /// Downloads requested matches data. In case of crash, data is saved every 100 records.
+    /// Up to "max_concurrent_fetches" matches are fetched at once, results are processed in
+    /// order of match_ids, so saved chunks are deterministic.
+    /// Failed attempts of matches which don't exist are recorded on the skip list, other failed
+    /// matches are left for the next update and returned. Fatal errors (e.g. invalid api key)
+    /// abort the retrieval.
     async fn get_match_data(
         &self,
         guild_id: &GuildId,
         match_ids: Vec<MatchId>,
-    ) -> Result<Vec<serde_json::Value>, BoxError> {
+    ) -> Result<(Vec<MatchDetails>, HashSet<MatchId>), BoxError> {
         let chunk_size = CONFIG
             .get_int("db_guild_data_chunk_size")
             .expect("Field db_guild_data_chunk_size not set in config.")
             as usize;
+        let max_concurrent_fetches = CONFIG.get_int("max_concurrent_fetches").unwrap_or(1) as usize;
+        let mut fetches = stream::iter(match_ids)
+            .map(|match_id| async move {
+                let info = self.provider.fetch_match_info(&match_id).await;
+                (match_id, info)
+            })
+            .buffered(max_concurrent_fetches.max(1));
         let mut not_cached_info = vec![];
+        let mut failed_ids = HashSet::new();
         let mut chunk = vec![];
-        for match_id in match_ids {
-            let new_info = self.od_client.fetch_match_info(&match_id).await?;
+        while let Some((match_id, info)) = fetches.next().await {
+            let new_info = match info {
+                Ok(info) => info,
+                Err(e) => {
+                    match e.downcast_ref::<FetchError>() {
+                        Some(fetch_error) if fetch_error.is_fatal() => return Err(e),
+                        Some(fetch_error) if fetch_error.is_permanent() => {
+                            warn!("Match {} not found, adding to skip list: {}", match_id, e);
+                            self.storage
+                                .add_skipped_match(&match_id, e.to_string())
+                                .await?;
+                        }
+                        _ => {
+                            warn!("Unable to fetch match {}, skipping: {}", match_id, e);
+                            failed_ids.insert(match_id);
+                        }
+                    }
+                    continue;
+                }
+            };
             chunk.push(new_info);
             if chunk.len() >= chunk_size {
                 self.storage.add_guild_data(guild_id, &chunk).await?;
@@ -50,42 +97,268 @@ impl DataRetriever {
         }
         self.storage.add_guild_data(guild_id, &chunk).await?;
         not_cached_info.extend(chunk.drain(0..));
-        Ok(not_cached_info)
+        Ok((not_cached_info, failed_ids))
+    }
+
+    /// Moves members sync state to their newest discovered match. Member state stays the same
+    /// if some of their matches couldn't be fetched, so they are discovered again next time.
+    async fn update_sync_state(
+        &self,
+        guild_id: &GuildId,
+        members_new_matches: &HashMap<PlayerId, Vec<MatchSummary>>,
+        failed_ids: &HashSet<MatchId>,
+    ) -> Result<(), BoxError> {
+        for (member_id, new_matches) in members_new_matches.iter() {
+            if new_matches
+                .iter()
+                .any(|player_match| failed_ids.contains(&player_match.match_id))
+            {
+                info!(
+                    "Not all matches of {} fetched, keeping sync state.",
+                    member_id
+                );
+                continue;
+            }
+            let last_match = match new_matches
+                .iter()
+                .max_by_key(|m| (m.start_time, m.match_id))
+            {
+                Some(last_match) => last_match,
+                None => continue,
+            };
+            let state = MemberSyncState::new(
+                guild_id,
+                member_id,
+                last_match.match_id,
+                last_match.start_time,
+            );
+            self.storage.set_member_sync_state(&state).await?;
+        }
+        Ok(())
+    }
+
+    /// Re-fetches matches submitted for parsing. Parsed ones replace cached copies, both in
+    /// storage and in guild_matches. Requests older than "parse_request_expiry_days" are dropped.
+    async fn refresh_parsed_matches(
+        &self,
+        guild_id: &GuildId,
+        guild_matches: &mut Vec<MatchDetails>,
+    ) -> Result<(), BoxError> {
+        let requests = self.storage.get_parse_requests(guild_id).await?;
+        if requests.is_empty() {
+            return Ok(());
+        }
+        let expiry_secs = CONFIG.get_int("parse_request_expiry_days").unwrap_or(7) * 86400;
+        let timestamp_now = Utc::now().timestamp();
+        let positions: HashMap<MatchId, usize> = guild_matches
+            .iter()
+            .enumerate()
+            .map(|(pos, match_)| (match_.match_id, pos))
+            .collect();
+        let mut replaced = 0;
+        for request in requests {
+            let match_id = request.match_id as MatchId;
+            let match_details = match self.provider.fetch_match_info(&match_id).await {
+                Ok(match_details) => match_details,
+                Err(e) => {
+                    warn!("Unable to re-fetch match {}: {}", match_id, e);
+                    continue;
+                }
+            };
+            if match_details.is_parsed() {
+                self.storage
+                    .replace_guild_match(guild_id, &match_details)
+                    .await?;
+                if let Some(pos) = positions.get(&match_id) {
+                    guild_matches[*pos] = match_details;
+                }
+                self.storage
+                    .remove_parse_request(guild_id, &match_id)
+                    .await?;
+                replaced += 1;
+            } else if timestamp_now - request.requested_at > expiry_secs {
+                info!("Match {} hasn't been parsed, dropping request.", match_id);
+                self.storage
+                    .remove_parse_request(guild_id, &match_id)
+                    .await?;
+            }
+        }
+        info!("Replaced {} matches with parsed data.", replaced);
+        Ok(())
+    }
+
+    /// Submits unparsed matches for parsing, newest first, up to "parse_requests_per_update"
+    /// per update. Matches older than "parse_request_max_age_days" are omitted, as their
+    /// replays are likely no longer available.
+    async fn request_parsing(
+        &self,
+        guild_id: &GuildId,
+        guild_matches: &Vec<MatchDetails>,
+    ) -> Result<(), BoxError> {
+        let budget = CONFIG.get_int("parse_requests_per_update").unwrap_or(0) as usize;
+        if budget == 0 {
+            return Ok(());
+        }
+        let max_age_secs = CONFIG.get_int("parse_request_max_age_days").unwrap_or(14) * 86400;
+        let timestamp_now = Utc::now().timestamp();
+        let pending_ids: HashSet<MatchId> = self
+            .storage
+            .get_parse_requests(guild_id)
+            .await?
+            .iter()
+            .map(|request| request.match_id as MatchId)
+            .collect();
+        let mut unparsed: Vec<(i64, MatchId)> = guild_matches
+            .iter()
+            .filter(|match_| !match_.is_parsed())
+            .filter_map(|match_| Some((match_.start_time?, match_.match_id)))
+            .filter(|(start_time, match_id)| {
+                timestamp_now - start_time <= max_age_secs && !pending_ids.contains(match_id)
+            })
+            .collect();
+        unparsed.sort();
+        unparsed.reverse();
+        for (_, match_id) in unparsed.into_iter().take(budget) {
+            match self.provider.request_match_parse(&match_id).await {
+                Ok(()) => self.storage.add_parse_request(guild_id, &match_id).await?,
+                Err(e) => warn!("Unable to request parse of match {}: {}", match_id, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets members of stratz guild from providers, or members of user defined group from db.
+    async fn get_members_ids(&self, guild_id: &GuildId) -> Result<Vec<PlayerId>, BoxError> {
+        if is_group_id(guild_id) {
+            Ok(self.storage.get_player_group(guild_id).await?.members)
+        } else {
+            self.provider.fetch_guild_members_ids(guild_id).await
+        }
+    }
+
+    /// Fetches and stores guild metadata. User defined groups are described by their name.
+    /// Failure doesn't stop the retrieval, as metadata isn't needed for the analysis.
+    async fn update_guild_info(&self, guild_id: &GuildId, members_count: usize) {
+        let guild_info = if is_group_id(guild_id) {
+            self.storage
+                .get_player_group(guild_id)
+                .await
+                .map(|group| GuildInfo {
+                    guild_id: guild_id.clone(),
+                    name: group.name.clone(),
+                    tag: group.name,
+                    logo_url: None,
+                    created: None,
+                    member_count: members_count as u32,
+                })
+        } else {
+            self.provider.fetch_guild_info(guild_id).await
+        };
+        let stored: Result<(), BoxError> = match guild_info {
+            Ok(guild_info) => self
+                .storage
+                .store_guild_info(&guild_info)
+                .await
+                .map_err(|e| e.into()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = stored {
+            warn!("Unable to update info of guild {}: {}", guild_id, e);
+        }
+    }
+
+    /// Searches guilds by tag or name.
+    pub async fn search_guilds(&self, query: &str) -> Result<Vec<GuildInfo>, BoxError> {
+        self.provider.search_guilds(query).await
     }
 
-    /// Gets match data for guild, either from db or opendota. Also saves missing match data to the db.
+    /// Gets profile of a former member. Profiles of current members are stored on each run,
+    /// so they are fetched only for members who left before their profile was stored.
+    async fn get_former_member_info(&self, player_id: &PlayerId) -> Result<PlayerInfo, BoxError> {
+        if let Some(player_info) = self.storage.get_player_info(player_id).await? {
+            return Ok(player_info);
+        }
+        let player_info = self.provider.fetch_player_info(player_id).await?;
+        self.storage
+            .store_player_info(player_id, &player_info)
+            .await?;
+        Ok(player_info)
+    }
+
+    /// Gets match data for guild, either from db or providers. Also saves missing match data to the db.
+    /// Only matches played after the last seen match of each member are discovered.
+    /// Unparsed matches are submitted for parsing and replaced once parsed.
+    /// Current members are stored as membership snapshot. New matches are discovered only for
+    /// current members, while former members are kept in the result with their tenures.
     pub async fn get_guild_raw_data(&self, guild_id: &GuildId) -> Result<GuildRawData, BoxError> {
-        let members_ids = self.od_client.fetch_guild_members_ids(guild_id).await?;
+        let members_ids = self.get_members_ids(guild_id).await?;
         info!("Got {} members of guild: {}", members_ids.len(), &guild_id);
+        self.update_guild_info(guild_id, members_ids.len()).await;
+        self.storage
+            .add_membership_snapshot(guild_id, &members_ids)
+            .await?;
+        let tenures = derive_tenures(&self.storage.get_membership_snapshots(guild_id).await?);
+        let former_members_ids: Vec<PlayerId> = tenures
+            .iter()
+            .map(|tenure| &tenure.player_id)
+            .filter(|player_id| !members_ids.contains(*player_id))
+            .unique()
+            .cloned()
+            .collect();
+        info!("Guild has {} former members.", former_members_ids.len());
+        let sync_states = self.storage.get_members_sync_state(guild_id).await?;
         let mut matches_of_interest = HashSet::new();
+        let mut members_new_matches = HashMap::new();
         let mut members = vec![];
         for member_id in members_ids.iter() {
-            for player_match_id in self.od_client.fetch_player_match_ids(member_id).await? {
-                matches_of_interest.insert(player_match_id);
+            let since = sync_states
+                .get(member_id)
+                .map(|state| state.last_start_time);
+            let player_matches = self.provider.fetch_player_matches(member_id, since).await?;
+            for player_match in player_matches.iter() {
+                matches_of_interest.insert(player_match.match_id);
             }
-            members.push(self.od_client.fetch_player_info(member_id).await?);
+            members_new_matches.insert(member_id.clone(), player_matches);
+            let player_info = self.provider.fetch_player_info(member_id).await?;
+            self.storage
+                .store_player_info(member_id, &player_info)
+                .await?;
+            members.push(player_info);
+        }
+        for member_id in former_members_ids.iter() {
+            members.push(self.get_former_member_info(member_id).await?);
         }
-        info!("Found {} matches.", matches_of_interest.len());
+        info!("Found {} new matches.", matches_of_interest.len());
 
         let mut cached_matches = self.storage.get_guild_data(guild_id).await?;
         let cached_ids: HashSet<MatchId> = cached_matches
             .iter()
-            .filter_map(|match_| match_["match_id"].as_i64())
-            .map(|id| id as u64)
+            .map(|match_| match_.match_id)
             .collect();
-        let not_cached_ids: Vec<MatchId> = matches_of_interest
+        let skipped_ids = self.storage.get_skipped_match_ids().await?;
+        let mut not_cached_ids: Vec<MatchId> = matches_of_interest
             .difference(&cached_ids)
+            .filter(|match_id| !skipped_ids.contains(match_id))
             .cloned()
             .collect();
+        not_cached_ids.sort();
         info!(
-            "Cached: {}. Not-cached: {}.",
+            "Cached: {}. Not-cached: {}. Skipped: {}.",
             cached_ids.len(),
-            not_cached_ids.len()
+            not_cached_ids.len(),
+            skipped_ids.len()
         );
-        let not_cached_info = self.get_match_data(guild_id, not_cached_ids).await?;
+        let (not_cached_info, failed_ids) = self.get_match_data(guild_id, not_cached_ids).await?;
         cached_matches.extend(not_cached_info.into_iter());
+        self.update_sync_state(guild_id, &members_new_matches, &failed_ids)
+            .await?;
+        self.refresh_parsed_matches(guild_id, &mut cached_matches)
+            .await?;
+        self.request_parsing(guild_id, &cached_matches).await?;
         Ok(GuildRawData {
             guild_id: guild_id.to_string(),
+            members_ids: members_ids.into_iter().chain(former_members_ids).collect(),
+            tenures,
             members,
             members_matches: cached_matches,
         })
@@ -1,67 +1,197 @@
 use crate::data_retrieval::data_retriever::GuildRawData;
-use crate::match_stats::{Match, MatchStats, PlayerStats};
+use crate::match_filter::MatchFilter;
+use crate::match_stats::{GuildMatches, Match, MatchStats, MemberNames, OtherHero, PlayerStats};
+use crate::opendota_models::{MatchDetails, MatchPlayer, PlayerInfo};
+use crate::storage::guild_membership_storage::MemberTenure;
+use crate::types::AccountId;
+use crate::CONFIG;
 
-use serde::de::Error;
-use serde_json::error::Error as serde_error;
 use serde_json::Result;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-type MemberName = String;
+/// Members tenures by account id, used to restrict members matches to their time in the guild.
+type MembersTenures = HashMap<AccountId, Vec<MemberTenure>>;
 
-/// Extracts single match stats, including only guild members information.
+/// Checks whether player was a guild member at the time of the match. Without tenures every
+/// member is taken into account.
+fn was_member(
+    member_ids: &HashSet<AccountId>,
+    tenures: &Option<MembersTenures>,
+    account_id: AccountId,
+    start_time: Option<i64>,
+) -> bool {
+    if !member_ids.contains(&account_id) {
+        return false;
+    }
+    match (tenures, start_time) {
+        (Some(tenures), Some(start_time)) => tenures.get(&account_id).map_or(true, |tenures| {
+            tenures.iter().any(|tenure| tenure.contains(start_time))
+        }),
+        _ => true,
+    }
+}
+
+/// Matches shorter than that are treated as abandoned.
+const MIN_MATCH_DURATION_SECS: u64 = 600;
+/// Leaver status of a player who abandoned the match.
+const ABANDON_LEAVER_STATUS: u64 = 2;
+
+/// Checks whether any of the players abandoned the match, or it ended in under ten minutes.
+fn is_abandoned(match_details: &MatchDetails) -> bool {
+    let has_leaver = match_details.players.iter().any(|player| {
+        player
+            .leaver_status
+            .map_or(false, |status| status >= ABANDON_LEAVER_STATUS)
+    });
+    let too_short = match_details
+        .duration
+        .map_or(false, |duration| duration < MIN_MATCH_DURATION_SECS);
+    has_leaver || too_short
+}
+
+/// Checks player side, using player slot if "isRadiant" field is missing.
+fn is_radiant(player: &MatchPlayer) -> Option<bool> {
+    match player.is_radiant {
+        Some(is_radiant) => Some(is_radiant),
+        None => player.player_slot.map(|slot| slot < 128),
+    }
+}
+
+/// Heroes of the players who aren't counted as members. Players with unknown hero or side
+/// are skipped.
+fn extract_other_heroes(others: &Vec<&MatchPlayer>) -> Vec<OtherHero> {
+    others
+        .iter()
+        .filter_map(|player| Some(OtherHero::new(player.hero_id?, is_radiant(player)?)))
+        .collect()
+}
+
+/// Reads member stats. Side is filled from the player slot if "isRadiant" field is missing.
+fn extract_player_stats(player: &MatchPlayer) -> Result<PlayerStats> {
+    PlayerStats::from_player(player, is_radiant(player))
+}
+
+/// Extracts single match stats. Players stats include only guild members information,
+/// other players are kept only as their heroes.
 fn extract_match_stats(
-    member_names: &Vec<MemberName>,
-    match_json: serde_json::Value,
+    member_ids: &HashSet<AccountId>,
+    tenures: &Option<MembersTenures>,
+    match_details: &MatchDetails,
 ) -> Result<Match> {
-    let match_players: &Vec<serde_json::Value> = match_json["players"]
-        .as_array()
-        .ok_or(serde_error::custom("No players data."))?;
-    let players_stats = match_players
+    let (members, others): (Vec<&MatchPlayer>, Vec<&MatchPlayer>) = match_details
+        .players
         .iter()
-        .filter(|player| player["personaname"].is_string())
-        .filter(|player| {
-            member_names.contains(&player["personaname"].as_str().unwrap().to_string())
-        })
-        .map(|player| serde_json::from_value(player.clone()))
+        .partition(|player| match player.account_id {
+            Some(account_id) => {
+                was_member(member_ids, tenures, account_id, match_details.start_time)
+            }
+            None => false,
+        });
+    let players_stats = members
+        .iter()
+        .map(|player| extract_player_stats(player))
         .collect::<Result<Vec<PlayerStats>>>()?;
-    let match_stats: MatchStats = serde_json::from_value(match_json)?;
-    Ok(Match::new(match_stats, players_stats))
+    let other_heroes = extract_other_heroes(&others);
+    Ok(Match::new(
+        MatchStats::from_details(match_details)?,
+        players_stats,
+        other_heroes,
+        is_abandoned(match_details),
+    ))
 }
 
-/// Extracts specific match data from dota open api match json's.
-pub fn extract_stats(guild_raw_data: GuildRawData) -> Result<Vec<Match>> {
-    let member_names: Vec<MemberName> = guild_raw_data
-        .members
+/// Reads members display names from their opendota profiles.
+fn extract_member_names(members: &Vec<PlayerInfo>) -> MemberNames {
+    let names: HashMap<AccountId, String> = members
         .iter()
-        .filter(|member| member["profile"]["personaname"].is_string())
-        .map(|member| {
-            member["profile"]["personaname"]
-                .as_str()
-                .unwrap()
-                .to_string()
+        .filter_map(|member| {
+            let profile = member.profile.as_ref()?;
+            Some((profile.account_id, profile.personaname.clone()?))
         })
         .collect();
+    MemberNames::new(names)
+}
+
+/// Extracts specific match data from dota open api match json's.
+pub fn extract_stats(guild_raw_data: GuildRawData) -> Result<GuildMatches> {
+    let member_ids: HashSet<AccountId> = guild_raw_data
+        .members_ids
+        .iter()
+        .filter_map(|member_id| member_id.parse().ok())
+        .collect();
+    let member_names = extract_member_names(&guild_raw_data.members);
+    let tenures = if CONFIG
+        .get_bool("restrict_matches_to_tenure")
+        .unwrap_or(false)
+    {
+        let mut tenures = MembersTenures::new();
+        for tenure in guild_raw_data.tenures.iter() {
+            if let Ok(account_id) = tenure.player_id.parse() {
+                tenures
+                    .entry(account_id)
+                    .or_insert(vec![])
+                    .push(tenure.clone());
+            }
+        }
+        Some(tenures)
+    } else {
+        None
+    };
+    let match_filter = MatchFilter::for_guild(&guild_raw_data.guild_id);
+    info!("Using {:?}", match_filter);
     let mut matches_stats = vec![];
     let mut parsed = 0;
+    let mut skipped = 0;
+    let mut filtered_out = 0;
     let total = guild_raw_data.members_matches.len();
-    let mut parsed_team_size = [0; 5];
-    for match_json in guild_raw_data.members_matches {
-        let match_stats = match extract_match_stats(&member_names, match_json.clone()) {
+    let mut parsed_team_size: BTreeMap<usize, u32> = BTreeMap::new();
+    let mut members_versus = 0;
+    for match_details in guild_raw_data.members_matches.iter() {
+        let match_stats = match extract_match_stats(&member_ids, &tenures, match_details) {
             Ok(m) => {
                 parsed += 1;
                 m
             }
-            Err(_) => continue,
+            Err(e) => {
+                warn!("Skipping match {}: {}", match_details.match_id, e);
+                skipped += 1;
+                continue;
+            }
         };
-        parsed_team_size[match_stats.get_team_size() - 1] += 1;
+        if match_stats.get_team_size() == 0 {
+            // All members played the match outside their tenure.
+            continue;
+        }
+        if !match_filter.accepts(&match_stats) {
+            filtered_out += 1;
+            continue;
+        }
+        if let Ok(teams) = match_stats.get_teams() {
+            if teams.len() > 1 {
+                members_versus += 1;
+            }
+            for team in teams {
+                *parsed_team_size
+                    .entry(team.get_players().len())
+                    .or_insert(0) += 1;
+            }
+        }
         matches_stats.push(match_stats);
     }
     info!("Parsed team sizes: {:?}", parsed_team_size);
-    info!("Parsed {} out of {} matches", parsed, total);
+    info!("Matches with members on both sides: {}", members_versus);
+    info!(
+        "Parsed {} out of {} matches, skipped {} invalid",
+        parsed, total, skipped
+    );
+    info!("Filtered out {} matches", filtered_out);
     let parsing_stats = compute_parsing_stats(&matches_stats);
     info!("{:#?}", parsing_stats);
-    Ok(matches_stats)
+    Ok(GuildMatches {
+        matches: matches_stats,
+        member_names,
+    })
 }
 
 /// Counts fields occurences. Used to check whether some field occurs frequent enough to be useful.
@@ -0,0 +1,186 @@
+use crate::data_retrieval::http_transport::HttpResponse;
+use crate::opendota_models::ModelError;
+use crate::CONFIG;
+use rand::Rng;
+use std::fmt;
+use std::future::Future;
+use tokio::time::{delay_for, Duration};
+
+const BACKOFF_BASE_SECS: f64 = 1.;
+const BACKOFF_MAX_SECS: f64 = 120.;
+
+/// Classified error of a single api request.
+#[derive(Debug)]
+pub enum FetchError {
+    /// Server responded with 429. Contains delay requested by Retry-After header.
+    RateLimited(Option<Duration>),
+    /// Server responded with 5xx.
+    ServerError(u16),
+    /// Server responded with 404.
+    NotFound,
+    /// Server responded with 401 or 403, api key or token is missing or invalid.
+    Unauthorized(u16),
+    /// Server responded with other 4xx.
+    ClientError(u16),
+    /// Response body is not a valid json or lacks required fields.
+    MalformedJson(String),
+    /// Request couldn't be sent or response couldn't be read.
+    Transport(String),
+    /// Provider doesn't support requested operation.
+    Unsupported(String),
+}
+
+impl FetchError {
+    /// Whether request may succeed if sent again later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            FetchError::RateLimited(_)
+            | FetchError::ServerError(_)
+            | FetchError::Transport(_)
+            | FetchError::MalformedJson(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether requested data doesn't exist. Such matches are put on the skip list.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            FetchError::NotFound | FetchError::ClientError(422) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether no request can succeed until configuration is fixed. Such errors abort the job.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            FetchError::Unauthorized(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Classifies response by its status code.
+    pub fn check_response(response: &HttpResponse) -> Result<(), FetchError> {
+        match response.status {
+            200..=299 => Ok(()),
+            401 | 403 => Err(FetchError::Unauthorized(response.status)),
+            404 => Err(FetchError::NotFound),
+            429 => Err(FetchError::RateLimited(
+                response
+                    .headers
+                    .get("retry-after")
+                    .and_then(|secs| secs.parse().ok())
+                    .map(Duration::from_secs),
+            )),
+            status if status >= 500 => Err(FetchError::ServerError(status)),
+            status => Err(FetchError::ClientError(status)),
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::RateLimited(_) => write!(f, "Rate limited by the api"),
+            FetchError::ServerError(status) => write!(f, "Api server error: {}", status),
+            FetchError::NotFound => write!(f, "Not found"),
+            FetchError::Unauthorized(status) => write!(f, "Api unauthorized: {}", status),
+            FetchError::ClientError(status) => write!(f, "Api client error: {}", status),
+            FetchError::MalformedJson(e) => write!(f, "Malformed json: {}", e),
+            FetchError::Transport(e) => write!(f, "Transport error: {}", e),
+            FetchError::Unsupported(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<ModelError> for FetchError {
+    fn from(e: ModelError) -> Self {
+        FetchError::MalformedJson(e.to_string())
+    }
+}
+
+/// Maximal number of retries of a single request, set by "fetch_max_retries" config field.
+pub fn max_retries() -> u32 {
+    CONFIG.get_int("fetch_max_retries").unwrap_or(5) as u32
+}
+
+/// Exponential backoff with full jitter.
+fn backoff_delay(attempt: u32) -> Duration {
+    let max_delay = (BACKOFF_BASE_SECS * 2f64.powi(attempt as i32)).min(BACKOFF_MAX_SECS);
+    Duration::from_secs_f64(rand::thread_rng().gen_range(0., max_delay))
+}
+
+/// Sends request until it succeeds, fails with not retryable error or max_retries is exceeded.
+pub async fn with_retries<T, F, Fut>(max_retries: u32, request: F) -> Result<T, FetchError>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<T, FetchError>>,
+{
+    let mut attempt = 0;
+    loop {
+        match request().await {
+            Ok(res) => return Ok(res),
+            Err(e) if e.is_retryable() && attempt < max_retries => {
+                let delay = match &e {
+                    FetchError::RateLimited(Some(retry_after)) => *retry_after,
+                    _ => backoff_delay(attempt),
+                };
+                warn!(
+                    "{}. Retry {}/{} in {:.1}s.",
+                    e,
+                    attempt + 1,
+                    max_retries,
+                    delay.as_secs_f64()
+                );
+                delay_for(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn response(status: u16) -> HttpResponse {
+        HttpResponse {
+            status,
+            headers: HashMap::new(),
+            body: String::new(),
+        }
+    }
+
+    fn classify(status: u16) -> FetchError {
+        FetchError::check_response(&response(status)).unwrap_err()
+    }
+
+    #[test]
+    fn auth_errors_are_fatal_not_permanent() {
+        for status in [401, 403].iter() {
+            let error = classify(*status);
+            assert!(error.is_fatal());
+            assert!(!error.is_permanent());
+            assert!(!error.is_retryable());
+        }
+    }
+
+    #[test]
+    fn only_missing_data_is_permanent() {
+        assert!(classify(404).is_permanent());
+        assert!(classify(422).is_permanent());
+        assert!(!classify(400).is_permanent());
+        assert!(!classify(500).is_permanent());
+        assert!(!FetchError::MalformedJson("<html>".to_string()).is_permanent());
+    }
+
+    #[test]
+    fn malformed_body_is_retried() {
+        assert!(FetchError::MalformedJson("truncated".to_string()).is_retryable());
+        assert!(classify(429).is_retryable());
+        assert!(classify(503).is_retryable());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::BoxError;
+use crate::CONFIG;
+use regex::Regex;
+use reqwest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Modes in which HttpTransport can operate. Selected by "http_transport_mode" config field.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransportMode {
+    /// Requests are sent to the remote api.
+    Live,
+    /// Requests are sent to the remote api and every request-response pair is saved to the cassette directory.
+    Record,
+    /// Responses are served from the cassette directory, nothing is sent over the network.
+    Replay,
+}
+
+impl TransportMode {
+    pub fn from_name(name: &str) -> Result<Self, BoxError> {
+        match name {
+            "live" => Ok(TransportMode::Live),
+            "record" => Ok(TransportMode::Record),
+            "replay" => Ok(TransportMode::Replay),
+            _ => Err(format!("Unknown http transport mode: {}", name).into()),
+        }
+    }
+}
+
+/// Http response, either received from the remote api or read from the cassette.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// Single recorded request-response pair, stored as one json file in the cassette directory.
+#[derive(Serialize, Deserialize)]
+struct CassetteEntry {
+    method: String,
+    url: String,
+    body: Option<String>,
+    response: HttpResponse,
+}
+
+/// Layer under api clients which sends http requests. Allows recording and replaying
+/// communication, so retrieval can run without the internet.
+pub struct HttpTransport {
+    mode: TransportMode,
+    cassette_dir: PathBuf,
+    client: reqwest::Client,
+}
+
+lazy_static! {
+    static ref API_KEY_PARAM: Regex = Regex::new(r"[?&]api_key=[^&]*$").unwrap();
+}
+
+/// Removes api key from url, so it doesn't end up in cassettes and logs.
+fn redact_url(url: &str) -> String {
+    API_KEY_PARAM.replace(url, "").to_string()
+}
+
+/// 64 bit FNV-1a hash. Used for cassette file names, as it is stable between builds.
+fn fnv1a(data: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in data.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+impl HttpTransport {
+    pub fn new(mode: TransportMode, cassette_dir: PathBuf) -> Self {
+        Self {
+            mode,
+            cassette_dir,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Creates transport using "http_transport_mode" ("live", "record" or "replay")
+    /// and "http_cassette_dir" config fields. Defaults to live mode.
+    pub fn from_config() -> Self {
+        let mode = match CONFIG.get_str("http_transport_mode") {
+            Ok(name) => {
+                TransportMode::from_name(&name).expect("Field http_transport_mode is invalid.")
+            }
+            Err(_) => TransportMode::Live,
+        };
+        let cassette_dir = CONFIG
+            .get_str("http_cassette_dir")
+            .unwrap_or_else(|_| "cassettes".to_string());
+        if mode != TransportMode::Live {
+            info!(
+                "Http transport in {:?} mode, cassette: {}",
+                mode, cassette_dir
+            );
+        }
+        HttpTransport::new(mode, PathBuf::from(cassette_dir))
+    }
+
+    pub fn mode(&self) -> TransportMode {
+        self.mode
+    }
+
+    /// Sends get request.
+    pub async fn get(&self, url: &str) -> Result<HttpResponse, BoxError> {
+        self.send("GET", url, None).await
+    }
+
+    /// Sends post request with json body.
+    pub async fn post_json(&self, url: &str, body: String) -> Result<HttpResponse, BoxError> {
+        self.send("POST", url, Some(body)).await
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<String>,
+    ) -> Result<HttpResponse, BoxError> {
+        let redacted_url = redact_url(url);
+        let cassette_path = self.cassette_path(method, &redacted_url, &body);
+        if self.mode == TransportMode::Replay {
+            let entry = match fs::read_to_string(&cassette_path).await {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("No recorded response for {} {}", method, redacted_url);
+                    return Err(Box::new(e));
+                }
+            };
+            let entry: CassetteEntry = serde_json::from_str(&entry)?;
+            return Ok(entry.response);
+        }
+        let response = self.send_remote(method, url, &body).await?;
+        if self.mode == TransportMode::Record {
+            let entry = CassetteEntry {
+                method: method.to_string(),
+                url: redacted_url,
+                body,
+                response: response.clone(),
+            };
+            fs::create_dir_all(&self.cassette_dir).await?;
+            fs::write(&cassette_path, serde_json::to_string_pretty(&entry)?).await?;
+        }
+        Ok(response)
+    }
+
+    async fn send_remote(
+        &self,
+        method: &str,
+        url: &str,
+        body: &Option<String>,
+    ) -> Result<HttpResponse, BoxError> {
+        let request = match method {
+            "POST" => self
+                .client
+                .post(url)
+                .header("content-type", "application/json")
+                .body(body.clone().unwrap_or_default()),
+            _ => self.client.get(url),
+        };
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+        let body = response.text().await?;
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// Path of the cassette file for request. Requests are identified by method, url and body.
+    fn cassette_path(&self, method: &str, url: &str, body: &Option<String>) -> PathBuf {
+        let key = format!("{} {} {}", method, url, body.as_deref().unwrap_or(""));
+        self.cassette_dir.join(format!(
+            "{}_{:016x}.json",
+            method.to_lowercase(),
+            fnv1a(&key)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_api_key() {
+        assert_eq!(
+            redact_url("https://api.opendota.com/api/matches/1?api_key=secret"),
+            "https://api.opendota.com/api/matches/1"
+        );
+        assert_eq!(
+            redact_url("https://api.opendota.com/api/players/1/matches?limit=10&api_key=secret"),
+            "https://api.opendota.com/api/players/1/matches?limit=10"
+        );
+        assert_eq!(
+            redact_url("https://api.opendota.com/api/matches/1"),
+            "https://api.opendota.com/api/matches/1"
+        );
+    }
+
+    #[test]
+    fn fnv1a_is_stable() {
+        assert_eq!(fnv1a(""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn cassette_key_is_same_in_record_and_replay() {
+        let url = "https://api.opendota.com/api/matches/5000000001";
+        let record = HttpTransport::new(TransportMode::Record, PathBuf::from("cassettes"));
+        let replay = HttpTransport::new(TransportMode::Replay, PathBuf::from("cassettes"));
+        let recorded =
+            record.cassette_path("GET", &redact_url(&format!("{}?api_key=a", url)), &None);
+        let replayed = replay.cassette_path("GET", &redact_url(url), &None);
+        assert_eq!(recorded, replayed);
+        assert_eq!(
+            recorded,
+            PathBuf::from("cassettes/get_87704eac50b47e1d.json")
+        );
+    }
+
+    #[test]
+    fn cassette_key_depends_on_body() {
+        let transport = HttpTransport::new(TransportMode::Replay, PathBuf::from("cassettes"));
+        let url = "https://api.stratz.com/graphql";
+        assert_ne!(
+            transport.cassette_path("POST", url, &Some("{}".to_string())),
+            transport.cassette_path("POST", url, &Some("{\"query\":1}".to_string()))
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use crate::data_retrieval::match_data_provider::MatchDataProvider;
+use crate::opendota_models::{from_json, FromObject, MatchDetails, MatchSummary, PlayerInfo};
+use crate::types::{AccountId, GuildId, MatchId, PlayerId};
+use crate::BoxError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::stream::StreamExt;
+use tokio::sync::Mutex;
+
+/// Provides data from local dumps. Expected directory layout:
+/// {dir}/guilds/{guild_id}.json - json array of members steam ids,
+/// {dir}/players/{player_id}.json - opendota player profile,
+/// {dir}/matches/{match_id}.json - opendota match data.
+pub struct LocalFilesProvider {
+    dir: PathBuf,
+    /// Matches of each player, built on first request by scanning matches directory.
+    player_matches: Mutex<Option<HashMap<AccountId, Vec<MatchSummary>>>>,
+}
+
+impl LocalFilesProvider {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            player_matches: Mutex::new(None),
+        }
+    }
+
+    async fn read_json(&self, path: PathBuf) -> Result<serde_json::Value, BoxError> {
+        let content = fs::read_to_string(&path).await?;
+        match serde_json::from_str(&content) {
+            Ok(json) => Ok(json),
+            Err(e) => {
+                warn!("Unable to parse local file: {}", path.display());
+                Err(Box::new(e))
+            }
+        }
+    }
+
+    async fn read_model<T: FromObject>(&self, path: PathBuf) -> Result<T, BoxError> {
+        let json = self.read_json(path.clone()).await?;
+        match from_json(json) {
+            Ok(model) => Ok(model),
+            Err(e) => {
+                warn!("Unable to read local file: {}", path.display());
+                Err(Box::new(e))
+            }
+        }
+    }
+
+    /// Reads all local matches once and groups them by players who took part in them.
+    async fn index_player_matches(
+        &self,
+    ) -> Result<HashMap<AccountId, Vec<MatchSummary>>, BoxError> {
+        let mut index: HashMap<AccountId, Vec<MatchSummary>> = HashMap::new();
+        let mut entries = fs::read_dir(self.dir.join("matches")).await?;
+        while let Some(entry) = entries.next().await {
+            let match_details: MatchDetails = match self.read_model(entry?.path()).await {
+                Ok(match_details) => match_details,
+                Err(_) => continue,
+            };
+            let start_time = match_details.start_time.unwrap_or_default();
+            for account_id in match_details
+                .players
+                .iter()
+                .filter_map(|player| player.account_id)
+            {
+                index
+                    .entry(account_id)
+                    .or_default()
+                    .push(MatchSummary::new(match_details.match_id, start_time));
+            }
+        }
+        Ok(index)
+    }
+}
+
+#[async_trait]
+impl MatchDataProvider for LocalFilesProvider {
+    fn name(&self) -> &'static str {
+        "local_files"
+    }
+
+    async fn fetch_guild_members_ids(&self, guild_id: &GuildId) -> Result<Vec<PlayerId>, BoxError> {
+        let members = self
+            .read_json(self.dir.join("guilds").join(format!("{}.json", guild_id)))
+            .await?;
+        Ok(members
+            .as_array()
+            .ok_or("Local guild file is not json array.")?
+            .iter()
+            .map(|id| match id {
+                serde_json::Value::String(id) => Some(id.clone()),
+                id => Some(id.as_u64()?.to_string()),
+            })
+            .collect::<Option<Vec<PlayerId>>>()
+            .ok_or("Local guild file contains invalid member id.")?)
+    }
+
+    async fn fetch_player_info(&self, player_id: &PlayerId) -> Result<PlayerInfo, BoxError> {
+        self.read_model(self.dir.join("players").join(format!("{}.json", player_id)))
+            .await
+    }
+
+    /// Returns local matches in which player took part. Matches directory is scanned only once.
+    async fn fetch_player_matches(
+        &self,
+        player_id: &PlayerId,
+        since: Option<i64>,
+    ) -> Result<Vec<MatchSummary>, BoxError> {
+        let account_id: AccountId = player_id.parse()?;
+        let mut player_matches = self.player_matches.lock().await;
+        if player_matches.is_none() {
+            *player_matches = Some(self.index_player_matches().await?);
+        }
+        Ok(player_matches
+            .as_ref()
+            .and_then(|index| index.get(&account_id))
+            .map(|matches| {
+                matches
+                    .iter()
+                    .filter(|summary| since.map_or(true, |since| summary.start_time >= since))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn fetch_match_info(&self, match_id: &MatchId) -> Result<MatchDetails, BoxError> {
+        self.read_model(self.dir.join("matches").join(format!("{}.json", match_id)))
+            .await
+    }
+}
@@ -0,0 +1,294 @@
+use crate::data_retrieval::fetch_error::FetchError;
+use crate::data_retrieval::local_files_provider::LocalFilesProvider;
+use crate::data_retrieval::opendota_client::OpenDotaClient;
+use crate::data_retrieval::stratz_client::StratzClient;
+use crate::opendota_models::{MatchDetails, MatchSummary, PlayerInfo};
+use crate::storage::guild_info_storage::GuildInfo;
+use crate::types::{GuildId, MatchId, PlayerId};
+use crate::BoxError;
+use crate::CONFIG;
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+/// Source of guild members and their matches data. Returned models are in opendota format.
+#[async_trait]
+pub trait MatchDataProvider: Send + Sync {
+    /// Name used in logs and config.
+    fn name(&self) -> &'static str;
+
+    async fn fetch_guild_members_ids(&self, guild_id: &GuildId) -> Result<Vec<PlayerId>, BoxError>;
+
+    async fn fetch_player_info(&self, player_id: &PlayerId) -> Result<PlayerInfo, BoxError>;
+
+    /// Returns matches of player. When since timestamp is provided, only matches started
+    /// after it have to be returned.
+    async fn fetch_player_matches(
+        &self,
+        player_id: &PlayerId,
+        since: Option<i64>,
+    ) -> Result<Vec<MatchSummary>, BoxError>;
+
+    async fn fetch_match_info(&self, match_id: &MatchId) -> Result<MatchDetails, BoxError>;
+
+    /// Returns guild metadata. Not every provider supports it.
+    async fn fetch_guild_info(&self, _guild_id: &GuildId) -> Result<GuildInfo, BoxError> {
+        Err(FetchError::Unsupported(format!("{} doesn't provide guild info.", self.name())).into())
+    }
+
+    /// Searches guilds by tag or name. Not every provider supports it.
+    async fn search_guilds(&self, _query: &str) -> Result<Vec<GuildInfo>, BoxError> {
+        Err(
+            FetchError::Unsupported(format!("{} doesn't support guild search.", self.name()))
+                .into(),
+        )
+    }
+
+    /// Submits match to be parsed from its replay. Not every provider supports it.
+    async fn request_match_parse(&self, _match_id: &MatchId) -> Result<(), BoxError> {
+        Err(
+            FetchError::Unsupported(format!("{} doesn't support parse requests.", self.name()))
+                .into(),
+        )
+    }
+}
+
+/// Checks whether provider failed because it doesn't support the operation.
+fn is_unsupported(error: &BoxError) -> bool {
+    match error.downcast_ref::<FetchError>() {
+        Some(FetchError::Unsupported(_)) => true,
+        _ => false,
+    }
+}
+
+/// Checks whether provider will never succeed with the same request.
+fn is_permanent(error: &BoxError) -> bool {
+    match error.downcast_ref::<FetchError>() {
+        Some(fetch_error) => fetch_error.is_permanent(),
+        None => false,
+    }
+}
+
+/// Checks whether provider can't succeed until its configuration is fixed.
+fn is_fatal(error: &BoxError) -> bool {
+    match error.downcast_ref::<FetchError>() {
+        Some(fetch_error) => fetch_error.is_fatal(),
+        None => false,
+    }
+}
+
+/// Tries providers one by one, until one of them succeeds. Providers which don't support
+/// the operation are skipped silently. Temporary errors are returned first, so request isn't
+/// given up while some provider may still succeed. Permanent error is returned only if every
+/// provider failed permanently.
+macro_rules! try_providers {
+    ($self:ident, $method:ident, $($arg:expr),+) => {{
+        let mut temporary_error: Option<BoxError> = None;
+        let mut fatal_error: Option<BoxError> = None;
+        let mut permanent_error: Option<BoxError> = None;
+        let mut unsupported: Option<BoxError> = None;
+        for provider in $self.providers.iter() {
+            match provider.$method($($arg),+).await {
+                Ok(res) => return Ok(res),
+                Err(e) if is_unsupported(&e) => unsupported = Some(e),
+                Err(e) => {
+                    warn!(
+                        "Provider {} failed at {}: {}",
+                        provider.name(),
+                        stringify!($method),
+                        e
+                    );
+                    if is_permanent(&e) {
+                        permanent_error = Some(e);
+                    } else if is_fatal(&e) {
+                        fatal_error = Some(e);
+                    } else {
+                        temporary_error = Some(e);
+                    }
+                }
+            }
+        }
+        Err(temporary_error
+            .or(fatal_error)
+            .or(permanent_error)
+            .or(unsupported)
+            .unwrap_or_else(|| "No match data providers configured.".into()))
+    }};
+}
+
+/// Provider which falls back to the next one, when previous is down.
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn MatchDataProvider>>,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<Box<dyn MatchDataProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// Creates providers listed in "match_data_providers" config field, in that order.
+    /// Possible values: "opendota", "stratz", "local_files". Local files are read from
+    /// "local_matches_dir" directory.
+    pub fn from_config() -> Self {
+        let names: Vec<String> = CONFIG
+            .get("match_data_providers")
+            .unwrap_or_else(|_| vec!["opendota".to_string(), "stratz".to_string()]);
+        let providers = names
+            .iter()
+            .map(|name| -> Box<dyn MatchDataProvider> {
+                match name.as_str() {
+                    "opendota" => Box::new(OpenDotaClient::new()),
+                    "stratz" => Box::new(StratzClient::new()),
+                    "local_files" => {
+                        let dir = CONFIG
+                            .get_str("local_matches_dir")
+                            .expect("Field local_matches_dir not set in config.");
+                        Box::new(LocalFilesProvider::new(PathBuf::from(dir)))
+                    }
+                    _ => panic!("Unknown match data provider: {}", name),
+                }
+            })
+            .collect();
+        FallbackProvider::new(providers)
+    }
+}
+
+#[async_trait]
+impl MatchDataProvider for FallbackProvider {
+    fn name(&self) -> &'static str {
+        "fallback"
+    }
+
+    async fn fetch_guild_members_ids(&self, guild_id: &GuildId) -> Result<Vec<PlayerId>, BoxError> {
+        try_providers!(self, fetch_guild_members_ids, guild_id)
+    }
+
+    async fn fetch_player_info(&self, player_id: &PlayerId) -> Result<PlayerInfo, BoxError> {
+        try_providers!(self, fetch_player_info, player_id)
+    }
+
+    async fn fetch_player_matches(
+        &self,
+        player_id: &PlayerId,
+        since: Option<i64>,
+    ) -> Result<Vec<MatchSummary>, BoxError> {
+        try_providers!(self, fetch_player_matches, player_id, since)
+    }
+
+    async fn fetch_match_info(&self, match_id: &MatchId) -> Result<MatchDetails, BoxError> {
+        try_providers!(self, fetch_match_info, match_id)
+    }
+
+    async fn fetch_guild_info(&self, guild_id: &GuildId) -> Result<GuildInfo, BoxError> {
+        try_providers!(self, fetch_guild_info, guild_id)
+    }
+
+    async fn search_guilds(&self, query: &str) -> Result<Vec<GuildInfo>, BoxError> {
+        try_providers!(self, search_guilds, query)
+    }
+
+    async fn request_match_parse(&self, match_id: &MatchId) -> Result<(), BoxError> {
+        try_providers!(self, request_match_parse, match_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Provider which fails to fetch any match with given error.
+    struct FailingProvider(fn() -> FetchError);
+
+    #[async_trait]
+    impl MatchDataProvider for FailingProvider {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn fetch_guild_members_ids(&self, _: &GuildId) -> Result<Vec<PlayerId>, BoxError> {
+            Err(FetchError::Unsupported("no guilds".to_string()).into())
+        }
+
+        async fn fetch_player_info(&self, _: &PlayerId) -> Result<PlayerInfo, BoxError> {
+            Err((self.0)().into())
+        }
+
+        async fn fetch_player_matches(
+            &self,
+            _: &PlayerId,
+            _: Option<i64>,
+        ) -> Result<Vec<MatchSummary>, BoxError> {
+            Err((self.0)().into())
+        }
+
+        async fn fetch_match_info(&self, _: &MatchId) -> Result<MatchDetails, BoxError> {
+            Err((self.0)().into())
+        }
+    }
+
+    fn not_found() -> FetchError {
+        FetchError::NotFound
+    }
+
+    fn unprocessable() -> FetchError {
+        FetchError::ClientError(422)
+    }
+
+    fn unauthorized() -> FetchError {
+        FetchError::Unauthorized(401)
+    }
+
+    fn server_error() -> FetchError {
+        FetchError::ServerError(503)
+    }
+
+    fn fallback(errors: Vec<fn() -> FetchError>) -> FallbackProvider {
+        FallbackProvider::new(
+            errors
+                .into_iter()
+                .map(|error| -> Box<dyn MatchDataProvider> { Box::new(FailingProvider(error)) })
+                .collect(),
+        )
+    }
+
+    async fn match_error(provider: &FallbackProvider) -> FetchError {
+        let error = provider.fetch_match_info(&1).await.unwrap_err();
+        match error.downcast::<FetchError>() {
+            Ok(fetch_error) => *fetch_error,
+            Err(e) => panic!("Not a fetch error: {}", e),
+        }
+    }
+
+    #[tokio::test]
+    async fn permanent_only_if_all_providers_failed_permanently() {
+        let provider = fallback(vec![not_found, unprocessable]);
+        assert!(match_error(&provider).await.is_permanent());
+    }
+
+    #[tokio::test]
+    async fn unauthorized_providers_are_fatal_not_permanent() {
+        let provider = fallback(vec![unauthorized, unauthorized]);
+        let error = match_error(&provider).await;
+        assert!(error.is_fatal());
+        assert!(!error.is_permanent());
+        let provider = fallback(vec![unauthorized, not_found]);
+        assert!(!match_error(&provider).await.is_permanent());
+    }
+
+    #[tokio::test]
+    async fn temporary_if_any_provider_failed_temporarily() {
+        let provider = fallback(vec![server_error, not_found]);
+        assert!(!match_error(&provider).await.is_permanent());
+        let provider = fallback(vec![not_found, server_error]);
+        assert!(!match_error(&provider).await.is_permanent());
+    }
+
+    #[tokio::test]
+    async fn unsupported_providers_are_skipped() {
+        let provider = fallback(vec![not_found]);
+        let error = provider
+            .fetch_guild_members_ids(&"1".to_string())
+            .await
+            .unwrap_err();
+        assert!(is_unsupported(&error));
+    }
+}
@@ -1,4 +1,12 @@
+pub mod constants_registry;
+pub mod constants_source;
 mod data_retriever;
 mod extractor;
+mod fetch_error;
+mod http_transport;
+mod local_files_provider;
+mod match_data_provider;
 mod opendota_client;
+mod rate_limiter;
 pub mod retrieval_agent;
+mod stratz_client;
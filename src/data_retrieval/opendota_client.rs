@@ -1,142 +1,236 @@
+use crate::data_retrieval::fetch_error::{max_retries, with_retries, FetchError};
+use crate::data_retrieval::http_transport::{HttpTransport, TransportMode};
+use crate::data_retrieval::match_data_provider::MatchDataProvider;
+use crate::data_retrieval::rate_limiter::RateLimiter;
+use crate::opendota_models::{from_json, from_json_array, MatchDetails, MatchSummary, PlayerInfo};
 use crate::types::{GuildId, MatchId, PlayerId};
 use crate::BoxError;
-use reqwest;
-use serde::de::Error;
-use serde_json::error::Error as serde_error;
-use tokio::time::{delay_until, Duration, Instant};
+use crate::CONFIG;
+use async_trait::async_trait;
+use chrono::Utc;
+
+lazy_static! {
+    /// Limiter shared by every OpenDotaClient instance.
+    static ref OPENDOTA_RATE_LIMITER: RateLimiter = RateLimiter::new(requests_per_minute());
+}
+
+/// Optional opendota api key, set by "opendota_api_key" config field.
+fn api_key() -> Option<String> {
+    CONFIG
+        .get_str("opendota_api_key")
+        .ok()
+        .filter(|key| !key.is_empty())
+}
+
+/// Allowed requests rate. Can be overriden by "opendota_requests_per_minute" config field,
+/// by default it is 60 rpm for free tier and 1200 rpm with api key.
+fn requests_per_minute() -> u32 {
+    match CONFIG.get_int("opendota_requests_per_minute") {
+        Ok(rpm) => rpm as u32,
+        Err(_) => match api_key() {
+            Some(_) => 1200,
+            None => 60,
+        },
+    }
+}
 
 /// Struct which handles all communication with opendota api.
-pub struct OpenDotaClient {}
+pub struct OpenDotaClient {
+    transport: HttpTransport,
+    api_key: Option<String>,
+}
 
 impl OpenDotaClient {
     pub fn new() -> Self {
-        Self {}
+        OpenDotaClient::with_transport(HttpTransport::from_config(), api_key())
     }
 
-    /// Sends get requests. Waits 1 second to ensure rpm <= 60.
-    async fn get_req_at60rpm(&self, url: &String) -> Result<serde_json::Value, BoxError> {
-        let start_inst = Instant::now();
-        let response = reqwest::get(url).await?.text().await?;
-        delay_until(start_inst + Duration::from_secs(1)).await;
-        let parsed_respone = match serde_json::from_str(&response) {
-            Ok(json) => json,
+    pub fn with_transport(transport: HttpTransport, api_key: Option<String>) -> Self {
+        Self { transport, api_key }
+    }
+
+    /// Sends get requests. Transient failures are retried with backoff.
+    async fn get_req(&self, url: &String) -> Result<serde_json::Value, BoxError> {
+        self.send_req(url, None).await
+    }
+
+    /// Sends post requests with json body. Transient failures are retried with backoff.
+    async fn post_req(&self, url: &String, body: String) -> Result<serde_json::Value, BoxError> {
+        self.send_req(url, Some(body)).await
+    }
+
+    async fn send_req(
+        &self,
+        url: &String,
+        body: Option<String>,
+    ) -> Result<serde_json::Value, BoxError> {
+        let replay = self.transport.mode() == TransportMode::Replay;
+        let max_retries = if replay { 0 } else { max_retries() };
+        Ok(with_retries(max_retries, || self.send_req_once(url, &body, replay)).await?)
+    }
+
+    /// Sends single request, post if it has body and get otherwise. Waits for the shared
+    /// rate limiter, unless responses are replayed.
+    async fn send_req_once(
+        &self,
+        url: &String,
+        body: &Option<String>,
+        replay: bool,
+    ) -> Result<serde_json::Value, FetchError> {
+        if !replay {
+            OPENDOTA_RATE_LIMITER.acquire().await;
+        }
+        let url_with_key = self.with_api_key(url);
+        let response = match body {
+            Some(body) => self.transport.post_json(&url_with_key, body.clone()).await,
+            None => self.transport.get(&url_with_key).await,
+        }
+        .map_err(|e| FetchError::Transport(e.to_string()))?;
+        if !replay {
+            OPENDOTA_RATE_LIMITER
+                .update_from_headers(&response.headers)
+                .await;
+        }
+        FetchError::check_response(&response)?;
+        match serde_json::from_str(&response.body) {
+            Ok(json) => Ok(json),
             Err(e) => {
                 warn!("Unable to parse response at url: {}", url);
-                return Err(Box::new(e));
+                Err(FetchError::MalformedJson(e.to_string()))
             }
-        };
-        Ok(parsed_respone)
+        }
     }
 
-    /// Retrieves members steam_id of provided guild via https://api.stratz.com/graphql post endpoint.
-    /// Request was designed by reverse engineering https://stratz.com/guilds/%guild_id%/members website.
-    pub async fn fetch_guild_members_ids(
-        &self,
-        guild_id: &GuildId,
-    ) -> Result<Vec<PlayerId>, BoxError> {
-        info!("Fetching members of guild: {}", guild_id);
-        let req_body = format!(
-            r#"{{
-            "operationName":"GuildMembers",
-            "variables":{{"id":{},"byId":true,"tag":"","byTag":false}},"query":"query GuildMembers($id: Int!, $byId: Boolean!, $tag: String!, $byTag: Boolean!) {{\n  guild(id: $id) @include(if: $byId) {{\n    ...GuildMembers\n  }}\n  stratz @include(if: $byTag) {{\n    search(request: {{query: $tag, searchType: [GUILDS]}}) {{\n      guild {{\n        ...GuildMembers\n      }}\n    }}\n  }}\n}}\n\nfragment GuildMembers on GuildType {{\n  members {{\n    steamAccount {{\n      id}}\n  }}\n}}\n"}}"#,
-            guild_id
-        );
-        let response = reqwest::Client::new()
-            .post("https://api.stratz.com/graphql")
-            .header("content-type", "application/json")
-            .body(req_body)
-            .send()
-            .await?
-            .text()
-            .await?;
-        let response_json: serde_json::Value = match serde_json::from_str(&response) {
-            Ok(json) => json,
-            Err(e) => {
-                warn!(
-                    "Unable to parse guild members response for guild: {}",
-                    guild_id
-                );
-                return Err(Box::new(e));
+    /// Appends api key as the last query parameter, so it can be stripped from recorded urls.
+    fn with_api_key(&self, url: &String) -> String {
+        match &self.api_key {
+            Some(key) => {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                format!("{}{}api_key={}", url, separator, key)
             }
-        };
-        let members = response_json["data"]["guild"]["members"]
-            .as_array()
-            .ok_or(serde_error::custom("unable to read members of guild"))?;
-        Ok(members
-            .iter()
-            .map(|v| {
-                Ok(v["steamAccount"]["id"]
-                    .as_u64()
-                    .ok_or(serde_error::custom("unable to guild member id"))?
-                    .to_string())
-            })
-            .collect::<Result<Vec<PlayerId>, serde_json::Error>>()?)
-    }
-
-    pub async fn fetch_player_info(
+            None => url.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl MatchDataProvider for OpenDotaClient {
+    fn name(&self) -> &'static str {
+        "opendota"
+    }
+
+    /// Opendota doesn't know about guilds.
+    async fn fetch_guild_members_ids(
         &self,
-        player_id: &PlayerId,
-    ) -> Result<serde_json::Value, BoxError> {
+        _guild_id: &GuildId,
+    ) -> Result<Vec<PlayerId>, BoxError> {
+        Err(FetchError::Unsupported("Opendota doesn't provide guild members.".to_string()).into())
+    }
+
+    /// Uses https://api.opendota.com/api/players/{player_id} endpoint.
+    async fn fetch_player_info(&self, player_id: &PlayerId) -> Result<PlayerInfo, BoxError> {
         info!("Fetching info about player: {}", player_id);
-        self.get_req_at60rpm(
-            &format!("https://api.opendota.com/api/players/{}", player_id).to_string(),
-        )
-        .await
+        let response = self
+            .get_req(&format!("https://api.opendota.com/api/players/{}", player_id).to_string())
+            .await?;
+        Ok(from_json(response).map_err(FetchError::from)?)
     }
 
-    /// Get match_ids of all games of a specified player.
-    /// Uses https://api.opendota.com/api/players/{player_id}/matches endpoint.
-    pub async fn fetch_player_match_ids(
+    /// Get matches of a specified player.
+    /// Uses https://api.opendota.com/api/players/{player_id}/matches endpoint. Matches since
+    /// timestamp are requested with "date" parameter, which is in days, so some already known
+    /// matches may be returned too. "significant" and "limit" parameters are set from
+    /// "opendota_significant_only" and "player_matches_limit" config fields.
+    async fn fetch_player_matches(
         &self,
         player_id: &PlayerId,
-    ) -> Result<Vec<MatchId>, BoxError> {
-        info!("Fetching match ids of player: {}", player_id);
-        let response = self
-            .get_req_at60rpm(
-                &format!("https://api.opendota.com/api/players/{}/matches", player_id).to_string(),
-            )
-            .await?;
-        Ok(response
-            .as_array()
-            .ok_or(serde_error::custom(
-                "unable to parse players match ids respone",
-            ))?
-            .iter()
-            .map(|v| {
-                v.as_object().ok_or(serde_error::custom(
-                    "players match id response is not object",
-                ))?["match_id"]
-                    .as_u64()
-                    .ok_or(serde_error::custom(
-                        "players match id response doesn't contain match_id",
-                    ))
-            })
-            .collect::<Result<Vec<MatchId>, serde_json::Error>>()?)
+        since: Option<i64>,
+    ) -> Result<Vec<MatchSummary>, BoxError> {
+        info!("Fetching matches of player: {}", player_id);
+        let mut params = vec![];
+        if let Some(since) = since {
+            let days = (Utc::now().timestamp() - since).max(0) / 86400 + 1;
+            params.push(format!("date={}", days));
+        }
+        if !CONFIG.get_bool("opendota_significant_only").unwrap_or(true) {
+            params.push("significant=0".to_string());
+        }
+        if let Ok(limit) = CONFIG.get_int("player_matches_limit") {
+            params.push(format!("limit={}", limit));
+        }
+        let mut url = format!("https://api.opendota.com/api/players/{}/matches", player_id);
+        if !params.is_empty() {
+            url = format!("{}?{}", url, params.join("&"));
+        }
+        let response = self.get_req(&url).await?;
+        Ok(from_json_array(response).map_err(FetchError::from)?)
     }
 
     /// Get single match data containing parsed match information.
     /// Uses https://api.opendota.com/api/matches/{match_id} endpoint.
-    pub async fn fetch_match_info(
-        &self,
-        match_id: &MatchId,
-    ) -> Result<serde_json::Value, BoxError> {
+    async fn fetch_match_info(&self, match_id: &MatchId) -> Result<MatchDetails, BoxError> {
         info!("Fetching match info: {}", match_id);
         let mut response = self
-            .get_req_at60rpm(
-                &format!("https://api.opendota.com/api/matches/{}", match_id).to_string(),
-            )
+            .get_req(&format!("https://api.opendota.com/api/matches/{}", match_id).to_string())
             .await?;
         if response["match_id"].is_null() {
             warn!("Match_id is missing, assiging: {}", match_id);
-            let obj = response.as_object_mut().ok_or(serde_error::custom(
-                "unable to cast match json to mutable object",
+            let obj = response.as_object_mut().ok_or(FetchError::MalformedJson(
+                "unable to cast match json to mutable object".to_string(),
             ))?;
             obj.insert(
                 "match_id".into(),
                 serde_json::Value::Number((*match_id as i64).into()),
             );
-            return Ok(serde_json::json!(obj));
         }
-        Ok(response)
+        Ok(from_json(response).map_err(FetchError::from)?)
+    }
+
+    /// Submits match for parsing via https://api.opendota.com/api/request/{match_id} endpoint.
+    async fn request_match_parse(&self, match_id: &MatchId) -> Result<(), BoxError> {
+        info!("Requesting parse of match: {}", match_id);
+        let url = format!("https://api.opendota.com/api/request/{}", match_id);
+        self.post_req(&url, String::new()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn replay_client(api_key: Option<String>) -> OpenDotaClient {
+        let cassette_dir =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cassettes");
+        OpenDotaClient::with_transport(
+            HttpTransport::new(TransportMode::Replay, cassette_dir),
+            api_key,
+        )
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_match() {
+        let client = replay_client(Some("secret".to_string()));
+        let match_details = client.fetch_match_info(&5000000001).await.unwrap();
+        assert_eq!(match_details.match_id, 5000000001);
+        assert_eq!(match_details.start_time, Some(1570000000));
+        assert_eq!(match_details.players.len(), 2);
+        assert_eq!(match_details.players[0].account_id, Some(100));
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_player() {
+        let client = replay_client(None);
+        let player_info = client.fetch_player_info(&"100".to_string()).await.unwrap();
+        let profile = player_info.profile.unwrap();
+        assert_eq!(profile.account_id, 100);
+        assert_eq!(profile.personaname, Some("first".to_string()));
+    }
+
+    #[tokio::test]
+    async fn missing_recording_fails_in_replay() {
+        let client = replay_client(None);
+        assert!(client.fetch_match_info(&1).await.is_err());
     }
 }
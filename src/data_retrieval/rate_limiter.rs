@@ -0,0 +1,130 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use tokio::time::{delay_for, Duration, Instant};
+
+/// Token bucket state. Tokens are refilled continuously at refill_per_sec rate.
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+    /// Set when the api reports that its limit is exhausted. No requests are sent before it.
+    paused_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Stops sending requests for given duration, unless pause lasts longer already.
+    fn pause(&mut self, duration: Duration) {
+        let until = Instant::now() + duration;
+        if self
+            .paused_until
+            .map_or(true, |paused_until| paused_until < until)
+        {
+            self.paused_until = Some(until);
+        }
+    }
+}
+
+/// Time left until the current minute ends, when the api minute limit is reset.
+fn until_next_minute() -> Duration {
+    let now = Utc::now();
+    let elapsed_ms = now.timestamp_millis().rem_euclid(60_000) as u64;
+    Duration::from_millis(60_000 - elapsed_ms)
+}
+
+/// Time left until the current month ends, when the api monthly limit is reset.
+fn until_next_month() -> Duration {
+    let now = Utc::now();
+    let (year, month) = match now.month() {
+        12 => (now.year() + 1, 1),
+        month => (now.year(), month + 1),
+    };
+    let next_month = NaiveDate::from_ymd(year, month, 1).and_hms(0, 0, 0);
+    (next_month - now.naive_utc())
+        .to_std()
+        .unwrap_or_else(|_| Duration::from_secs(0))
+}
+
+/// Token bucket rate limiter, meant to be shared by all clients of a single api.
+/// Bucket is adjusted using rate limit headers returned by the api.
+pub struct RateLimiter {
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    /// Allows requests_per_minute requests per minute, with bursts of up to one second worth of requests.
+    pub fn new(requests_per_minute: u32) -> Self {
+        let refill_per_sec = requests_per_minute as f64 / 60.;
+        let capacity = refill_per_sec.max(1.);
+        Self {
+            bucket: Mutex::new(Bucket {
+                capacity,
+                tokens: capacity,
+                refill_per_sec,
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    /// Waits until request can be sent.
+    pub async fn acquire(&self) {
+        loop {
+            let wait_secs = {
+                let mut bucket = self.bucket.lock().await;
+                if let Some(paused_until) = bucket.paused_until {
+                    let now = Instant::now();
+                    if paused_until > now {
+                        let pause = paused_until - now;
+                        drop(bucket);
+                        delay_for(pause).await;
+                        continue;
+                    }
+                    bucket.paused_until = None;
+                }
+                bucket.refill();
+                if bucket.tokens >= 1. {
+                    bucket.tokens -= 1.;
+                    return;
+                }
+                (1. - bucket.tokens) / bucket.refill_per_sec
+            };
+            delay_for(Duration::from_secs_f64(wait_secs)).await;
+        }
+    }
+
+    /// Reads opendota rate limit headers. When server reports less remaining requests
+    /// than bucket holds, bucket is drained accordingly. When minute or monthly limit
+    /// is exhausted, requests are paused until the limit is reset.
+    pub async fn update_from_headers(&self, headers: &HashMap<String, String>) {
+        let remaining = |header: &str| -> Option<f64> { headers.get(header)?.parse().ok() };
+        let mut bucket = self.bucket.lock().await;
+        if let Some(remaining_month) = remaining("x-rate-limit-remaining-month") {
+            if remaining_month <= 0. {
+                let pause = until_next_month();
+                warn!(
+                    "Monthly api requests limit has been reached. Requests paused for {}h.",
+                    pause.as_secs() / 3600
+                );
+                bucket.pause(pause);
+            }
+        }
+        if let Some(remaining_minute) = remaining("x-rate-limit-remaining-minute") {
+            bucket.refill();
+            if remaining_minute < bucket.tokens {
+                bucket.tokens = remaining_minute.max(0.);
+            }
+            if remaining_minute <= 0. {
+                bucket.pause(until_next_minute());
+            }
+        }
+    }
+}
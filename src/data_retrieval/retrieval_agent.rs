@@ -2,7 +2,8 @@ use crate::BoxError;
 use std::sync::Arc;
 
 use crate::data_retrieval::extractor::extract_stats;
-use crate::match_stats::Match;
+use crate::match_stats::GuildMatches;
+use crate::storage::guild_info_storage::GuildInfo;
 use crate::{data_retrieval::data_retriever::DataRetriever, storage::Storage};
 use lazy_static;
 use tokio::sync::Mutex;
@@ -15,11 +16,77 @@ lazy_static! {
 pub async fn process_guild_matches_retrieval(
     storage: Arc<Storage>,
     guild_id: &String,
-) -> Result<Vec<Match>, BoxError> {
+) -> Result<GuildMatches, BoxError> {
+    retrieve_guild_matches(DataRetriever::new(storage), guild_id).await
+}
+
+/// Runs whole retrieval scenario with given retriever. Only one retrieval runs at a time.
+async fn retrieve_guild_matches(
+    data_retriever: DataRetriever,
+    guild_id: &String,
+) -> Result<GuildMatches, BoxError> {
     let _lock = RETRIEVAL_MUTEX.lock().await;
-    let data_retriever = DataRetriever::new(storage);
     let guild_raw_data = data_retriever.get_guild_raw_data(&guild_id).await?;
     drop(_lock);
     let matches = extract_stats(guild_raw_data)?;
     Ok(matches)
 }
+
+/// Searches guilds by tag or name using match data providers.
+pub async fn search_guilds(storage: Arc<Storage>, query: &str) -> Result<Vec<GuildInfo>, BoxError> {
+    DataRetriever::new(storage).search_guilds(query).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_retrieval::http_transport::{HttpTransport, TransportMode};
+    use crate::data_retrieval::match_data_provider::{FallbackProvider, MatchDataProvider};
+    use crate::data_retrieval::opendota_client::OpenDotaClient;
+    use crate::data_retrieval::stratz_client::StratzClient;
+    use std::path::PathBuf;
+
+    const TEST_DATABASE: &str = "dotastats_replay_test";
+
+    fn replay_transport() -> HttpTransport {
+        let cassette_dir =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cassettes");
+        HttpTransport::new(TransportMode::Replay, cassette_dir)
+    }
+
+    /// Retrieves recorded guild 777 (members 100 and 200, two matches) from opendota and
+    /// stratz cassettes. Requires mongodb at DOTASTATS_TEST_MONGODB_URI, local one by default.
+    #[tokio::test]
+    #[ignore]
+    async fn retrieves_guild_from_cassettes() {
+        let uri = std::env::var("DOTASTATS_TEST_MONGODB_URI")
+            .unwrap_or_else(|_| "mongodb://127.0.0.1:27017".to_string());
+        let storage = Arc::new(Storage::connect(&uri, TEST_DATABASE).await.unwrap());
+        storage.drop_database().await.unwrap();
+        let providers: Vec<Box<dyn MatchDataProvider>> = vec![
+            Box::new(OpenDotaClient::with_transport(replay_transport(), None)),
+            Box::new(StratzClient::with_transport(replay_transport())),
+        ];
+        let data_retriever = DataRetriever::with_provider(
+            storage.clone(),
+            Box::new(FallbackProvider::new(providers)),
+        );
+        let guild_id = "777".to_string();
+        let guild_matches = retrieve_guild_matches(data_retriever, &guild_id)
+            .await
+            .unwrap();
+        assert_eq!(guild_matches.matches.len(), 2);
+        assert_eq!(guild_matches.member_names.get(&100), "first");
+        assert_eq!(guild_matches.member_names.get(&200), "second");
+        let mut team_sizes: Vec<(usize, bool)> = guild_matches
+            .matches
+            .iter()
+            .flat_map(|match_| match_.get_teams().unwrap())
+            .map(|team| (team.get_players().len(), team.is_won()))
+            .collect();
+        team_sizes.sort();
+        assert_eq!(team_sizes, vec![(1, true), (2, false)]);
+        assert_eq!(storage.get_guild_data(&guild_id).await.unwrap().len(), 2);
+        storage.drop_database().await.unwrap();
+    }
+}
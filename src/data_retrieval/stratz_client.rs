@@ -0,0 +1,383 @@
+use crate::data_retrieval::fetch_error::{max_retries, with_retries, FetchError};
+use crate::data_retrieval::http_transport::{HttpTransport, TransportMode};
+use crate::data_retrieval::match_data_provider::MatchDataProvider;
+use crate::data_retrieval::rate_limiter::RateLimiter;
+use crate::opendota_models::{from_json, MatchDetails, MatchSummary, PlayerInfo};
+use crate::storage::guild_info_storage::GuildInfo;
+use crate::types::{GuildId, MatchId, PlayerId};
+use crate::BoxError;
+use async_trait::async_trait;
+use serde::de::Error;
+use serde_json::error::Error as serde_error;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+lazy_static! {
+    /// Limiter shared by every StratzClient instance.
+    static ref STRATZ_RATE_LIMITER: RateLimiter = RateLimiter::new(60);
+}
+
+/// Stratz player match fields and their opendota counterparts.
+const PLAYER_FIELDS_MAPPING: [(&str, &str); 11] = [
+    ("steamAccountId", "account_id"),
+    ("heroId", "hero_id"),
+    ("isRadiant", "isRadiant"),
+    ("kills", "kills"),
+    ("deaths", "deaths"),
+    ("assists", "assists"),
+    ("numLastHits", "last_hits"),
+    ("numDenies", "denies"),
+    ("goldPerMinute", "gold_per_min"),
+    ("experiencePerMinute", "xp_per_min"),
+    ("level", "level"),
+];
+
+/// Struct which handles all communication with stratz api.
+pub struct StratzClient {
+    transport: HttpTransport,
+    /// Guilds json's fetched by id, so members and metadata of a guild are fetched with
+    /// a single query.
+    guilds: Mutex<HashMap<GuildId, serde_json::Value>>,
+}
+
+impl StratzClient {
+    pub fn new() -> Self {
+        StratzClient::with_transport(HttpTransport::from_config())
+    }
+
+    pub fn with_transport(transport: HttpTransport) -> Self {
+        Self {
+            transport,
+            guilds: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends guilds query to https://api.stratz.com/graphql post endpoint. Returns guilds json's.
+    async fn query_guilds(
+        &self,
+        id: i64,
+        by_id: bool,
+        tag: &str,
+    ) -> Result<Vec<serde_json::Value>, BoxError> {
+        let req_body = serde_json::json!({
+            "operationName": "GuildInfo",
+            "variables": {"id": id, "byId": by_id, "tag": tag, "byTag": !by_id},
+            "query": GUILDS_QUERY,
+        });
+        let response_json = self
+            .post_req(
+                &"https://api.stratz.com/graphql".to_string(),
+                req_body.to_string(),
+            )
+            .await?;
+        let guilds = if by_id {
+            &response_json["data"]["guild"]
+        } else {
+            &response_json["data"]["stratz"]["search"]["guild"]
+        };
+        Ok(match guilds {
+            serde_json::Value::Null => vec![],
+            serde_json::Value::Array(guilds) => guilds.clone(),
+            guild => vec![guild.clone()],
+        })
+    }
+
+    /// Returns json of guild with given id. Guild is queried only once by the client.
+    async fn query_guild_by_id(&self, guild_id: &GuildId) -> Result<serde_json::Value, BoxError> {
+        if let Some(guild) = self.guilds.lock().await.get(guild_id) {
+            return Ok(guild.clone());
+        }
+        let id = guild_id
+            .parse::<i64>()
+            .map_err(|_| format!("Invalid guild id: {}", guild_id))?;
+        let mut guilds = self.query_guilds(id, true, "").await?;
+        if guilds.is_empty() {
+            return Err(format!("Guild {} not found.", guild_id).into());
+        }
+        let guild = guilds.remove(0);
+        self.guilds
+            .lock()
+            .await
+            .insert(guild_id.clone(), guild.clone());
+        Ok(guild)
+    }
+
+    /// Sends get requests. Transient failures are retried with backoff.
+    async fn get_req(&self, url: &String) -> Result<serde_json::Value, BoxError> {
+        self.send_req(url, None).await
+    }
+
+    /// Sends post requests with json body. Transient failures are retried with backoff.
+    async fn post_req(&self, url: &String, body: String) -> Result<serde_json::Value, BoxError> {
+        self.send_req(url, Some(body)).await
+    }
+
+    async fn send_req(
+        &self,
+        url: &String,
+        body: Option<String>,
+    ) -> Result<serde_json::Value, BoxError> {
+        let replay = self.transport.mode() == TransportMode::Replay;
+        let max_retries = if replay { 0 } else { max_retries() };
+        Ok(with_retries(max_retries, || self.send_req_once(url, &body, replay)).await?)
+    }
+
+    /// Sends single request, post if it has body and get otherwise. Waits for the shared
+    /// rate limiter, unless responses are replayed.
+    async fn send_req_once(
+        &self,
+        url: &String,
+        body: &Option<String>,
+        replay: bool,
+    ) -> Result<serde_json::Value, FetchError> {
+        if !replay {
+            STRATZ_RATE_LIMITER.acquire().await;
+        }
+        let response = match body {
+            Some(body) => self.transport.post_json(url, body.clone()).await,
+            None => self.transport.get(url).await,
+        }
+        .map_err(|e| FetchError::Transport(e.to_string()))?;
+        FetchError::check_response(&response)?;
+        match serde_json::from_str(&response.body) {
+            Ok(json) => Ok(json),
+            Err(e) => {
+                warn!("Unable to parse response at url: {}", url);
+                Err(FetchError::MalformedJson(e.to_string()))
+            }
+        }
+    }
+}
+
+/// Graphql query returning guilds either by id or by tag/name search.
+/// Query was designed by reverse engineering https://stratz.com/guilds/%guild_id%/members website.
+const GUILDS_QUERY: &str =
+    "query GuildInfo($id: Int!, $byId: Boolean!, $tag: String!, $byTag: Boolean!) {
+  guild(id: $id) @include(if: $byId) {
+    ...GuildInfo
+  }
+  stratz @include(if: $byTag) {
+    search(request: {query: $tag, searchType: [GUILDS]}) {
+      guild {
+        ...GuildInfo
+      }
+    }
+  }
+}
+
+fragment GuildInfo on GuildType {
+  id
+  name
+  tag
+  logo
+  createdDateTime
+  members {
+    steamAccount {
+      id
+    }
+  }
+}
+";
+
+/// Reads guild metadata from stratz guild json.
+fn parse_guild_info(guild: &serde_json::Value) -> Result<GuildInfo, BoxError> {
+    Ok(GuildInfo {
+        guild_id: guild["id"]
+            .as_u64()
+            .ok_or(serde_error::custom("guild doesn't contain id"))?
+            .to_string(),
+        name: guild["name"].as_str().unwrap_or_default().to_string(),
+        tag: guild["tag"].as_str().unwrap_or_default().to_string(),
+        logo_url: guild["logo"].as_str().map(|logo| logo.to_string()),
+        created: guild["createdDateTime"].as_i64(),
+        member_count: guild["members"]
+            .as_array()
+            .map_or(0, |members| members.len() as u32),
+    })
+}
+
+/// Converts stratz match json to the opendota format, which is expected by the extractor.
+/// Only fields available in both apis are kept.
+fn to_opendota_match(stratz_match: &serde_json::Value) -> Result<serde_json::Value, FetchError> {
+    let radiant_win = stratz_match["didRadiantWin"]
+        .as_bool()
+        .ok_or(FetchError::MalformedJson(
+            "stratz match doesn't contain result".to_string(),
+        ))?;
+    let players = stratz_match["players"]
+        .as_array()
+        .ok_or(FetchError::MalformedJson(
+            "stratz match doesn't contain players".to_string(),
+        ))?
+        .iter()
+        .map(|player| {
+            let mut od_player = serde_json::Map::new();
+            for (stratz_field, od_field) in PLAYER_FIELDS_MAPPING.iter() {
+                if !player[stratz_field].is_null() {
+                    od_player.insert(od_field.to_string(), player[stratz_field].clone());
+                }
+            }
+            if let Some(name) = player["steamAccount"]["name"].as_str() {
+                od_player.insert("personaname".into(), name.into());
+            }
+            let is_radiant = player["isRadiant"].as_bool().unwrap_or_default();
+            od_player.insert("win".into(), ((is_radiant == radiant_win) as u64).into());
+            serde_json::Value::Object(od_player)
+        })
+        .collect::<Vec<serde_json::Value>>();
+    Ok(serde_json::json!({
+        "match_id": stratz_match["id"],
+        "radiant_win": radiant_win,
+        "duration": stratz_match["durationSeconds"],
+        "start_time": stratz_match["startDateTime"],
+        "game_mode": stratz_match["gameMode"],
+        "lobby_type": stratz_match["lobbyType"],
+        "region": stratz_match["regionId"],
+        "cluster": stratz_match["clusterId"],
+        "players": players,
+    }))
+}
+
+#[async_trait]
+impl MatchDataProvider for StratzClient {
+    fn name(&self) -> &'static str {
+        "stratz"
+    }
+
+    /// Retrieves members steam_id of provided guild via https://api.stratz.com/graphql post endpoint.
+    async fn fetch_guild_members_ids(&self, guild_id: &GuildId) -> Result<Vec<PlayerId>, BoxError> {
+        info!("Fetching members of guild: {}", guild_id);
+        let guild = self.query_guild_by_id(guild_id).await?;
+        let members = guild["members"]
+            .as_array()
+            .ok_or(serde_error::custom("unable to read members of guild"))?;
+        Ok(members
+            .iter()
+            .map(|v| {
+                Ok(v["steamAccount"]["id"]
+                    .as_u64()
+                    .ok_or(serde_error::custom("unable to guild member id"))?
+                    .to_string())
+            })
+            .collect::<Result<Vec<PlayerId>, serde_json::Error>>()?)
+    }
+
+    async fn fetch_guild_info(&self, guild_id: &GuildId) -> Result<GuildInfo, BoxError> {
+        info!("Fetching info of guild: {}", guild_id);
+        let guild = self.query_guild_by_id(guild_id).await?;
+        parse_guild_info(&guild)
+    }
+
+    async fn search_guilds(&self, query: &str) -> Result<Vec<GuildInfo>, BoxError> {
+        info!("Searching guilds: {}", query);
+        let guilds = self.query_guilds(0, false, query).await?;
+        guilds.iter().map(parse_guild_info).collect()
+    }
+
+    /// Uses https://api.stratz.com/api/v1/Player/{player_id} endpoint.
+    /// Returned info mimics opendota player profile.
+    async fn fetch_player_info(&self, player_id: &PlayerId) -> Result<PlayerInfo, BoxError> {
+        info!("Fetching info about player from stratz: {}", player_id);
+        let response = self
+            .get_req(&format!(
+                "https://api.stratz.com/api/v1/Player/{}",
+                player_id
+            ))
+            .await?;
+        let steam_account = &response["steamAccount"];
+        if steam_account.is_null() {
+            return Err(serde_error::custom("stratz player doesn't contain steamAccount").into());
+        }
+        let player_info = serde_json::json!({
+            "profile": {
+                "account_id": steam_account["id"],
+                "personaname": steam_account["name"],
+                "avatarfull": steam_account["avatar"],
+            }
+        });
+        Ok(from_json(player_info).map_err(FetchError::from)?)
+    }
+
+    /// Uses https://api.stratz.com/api/v1/Player/{player_id}/matches endpoint, which is paginated.
+    /// Matches are returned from the newest, so paging stops at first match older than since.
+    async fn fetch_player_matches(
+        &self,
+        player_id: &PlayerId,
+        since: Option<i64>,
+    ) -> Result<Vec<MatchSummary>, BoxError> {
+        info!("Fetching matches of player from stratz: {}", player_id);
+        const PAGE_SIZE: usize = 250;
+        let mut matches = vec![];
+        let mut skip = 0;
+        loop {
+            let response = self
+                .get_req(&format!(
+                    "https://api.stratz.com/api/v1/Player/{}/matches?take={}&skip={}",
+                    player_id, PAGE_SIZE, skip
+                ))
+                .await?;
+            let page = response
+                .as_array()
+                .ok_or(serde_error::custom(
+                    "unable to parse players matches respone",
+                ))?
+                .iter()
+                .map(|v| {
+                    Ok(MatchSummary::new(
+                        v["id"].as_u64().ok_or(serde_error::custom(
+                            "players matches response doesn't contain id",
+                        ))?,
+                        v["startDateTime"].as_i64().unwrap_or_default(),
+                    ))
+                })
+                .collect::<Result<Vec<MatchSummary>, serde_json::Error>>()?;
+            let page_len = page.len();
+            skip += page_len;
+            let mut reached_since = false;
+            for player_match in page {
+                match since {
+                    Some(since) if player_match.start_time < since => reached_since = true,
+                    _ => matches.push(player_match),
+                }
+            }
+            if page_len < PAGE_SIZE || reached_since {
+                break;
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Uses https://api.stratz.com/api/v1/match/{match_id} endpoint.
+    async fn fetch_match_info(&self, match_id: &MatchId) -> Result<MatchDetails, BoxError> {
+        info!("Fetching match info from stratz: {}", match_id);
+        let response = self
+            .get_req(&format!("https://api.stratz.com/api/v1/match/{}", match_id))
+            .await?;
+        let mut od_match = to_opendota_match(&response)?;
+        od_match["match_id"] = (*match_id).into();
+        Ok(from_json(od_match).map_err(FetchError::from)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn replay_client() -> StratzClient {
+        let cassette_dir =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cassettes");
+        StratzClient::with_transport(HttpTransport::new(TransportMode::Replay, cassette_dir))
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_guild() {
+        let client = replay_client();
+        let guild_id = "777".to_string();
+        let members = client.fetch_guild_members_ids(&guild_id).await.unwrap();
+        assert_eq!(members, vec!["100".to_string(), "200".to_string()]);
+        let guild_info = client.fetch_guild_info(&guild_id).await.unwrap();
+        assert_eq!(guild_info.tag, "RPL");
+        assert_eq!(guild_info.member_count, 2);
+    }
+}
@@ -1,11 +1,17 @@
 use serde_json;
 use std::collections::HashMap;
-use std::fs::read_to_string;
 
+use crate::data_retrieval::constants_registry::ConstantsRegistry;
 use crate::BoxError;
 use serde::de::Error;
 use serde_json::error::Error as serde_error;
 
+lazy_static! {
+    /// Heroes constants shared by all analyses. Loaded on first use.
+    pub static ref HEROES_REGISTRY: ConstantsRegistry<HeroesInfo> =
+        ConstantsRegistry::new("heroes", HeroesInfo::from_json);
+}
+
 /// Hero data relevant for analysis.
 #[derive(Clone, Default)]
 pub struct Hero {
@@ -13,27 +19,38 @@ pub struct Hero {
     pub roles: Vec<String>,
 }
 
-/// Contains map HeroId -> Hero
+impl Hero {
+    /// Placeholder of hero missing in constants.
+    pub fn unknown(hero_id: u64) -> Self {
+        Hero {
+            name: format!("Unknown hero #{}", hero_id),
+            roles: vec![],
+        }
+    }
+}
+
+/// Contains map HeroId -> Hero, read from given version of heroes constants.
 pub struct HeroesInfo {
     heroes: HashMap<u64, Hero>,
+    version: i64,
 }
 
 impl HeroesInfo {
-    /// Initializes from json file with heroes constants.
-    /// File may be retrieved by
-    /// > wget https://raw.githubusercontent.com/odota/dotaconstants/master/build/heroes.json
-    pub fn init(heroes_filename: String) -> Result<Self, BoxError> {
-        let raw_heroes: serde_json::Value =
-            serde_json::from_str(read_to_string(heroes_filename)?.as_str())?;
+    /// Initializes from heroes constants json, in format of
+    /// https://api.opendota.com/api/constants/heroes endpoint.
+    pub fn from_json(raw_heroes: &serde_json::Value, version: i64) -> Result<Self, BoxError> {
         let heroes_constants = raw_heroes
             .as_object()
             .ok_or(serde_error::custom("Heroes constants is not json object."))?;
         let mut heroes_info = HeroesInfo {
             heroes: HashMap::new(),
+            version,
         };
         for (id, hero) in heroes_constants {
             let hero_id = id.parse::<u64>()?;
-            assert!(hero_id > 0);
+            if hero_id == 0 {
+                return Err(serde_error::custom("hero_id 0 in heroes constants").into());
+            }
             let hero_parsed = Hero {
                 name: hero["localized_name"]
                     .as_str()
@@ -64,7 +81,12 @@ impl HeroesInfo {
         Ok(heroes_info)
     }
 
-    pub fn get_hero(&self, hero_id: u64) -> Hero {
-        self.heroes[&hero_id].clone()
+    /// Returns None for heroes missing in constants, e.g. released after constants version.
+    pub fn get_hero(&self, hero_id: u64) -> Option<Hero> {
+        self.heroes.get(&hero_id).cloned()
+    }
+
+    pub fn version(&self) -> i64 {
+        self.version
     }
 }
@@ -0,0 +1,85 @@
+use serde_json;
+use std::collections::HashMap;
+
+use crate::data_retrieval::constants_registry::ConstantsRegistry;
+use crate::BoxError;
+use serde::de::Error;
+use serde_json::error::Error as serde_error;
+
+lazy_static! {
+    /// Items constants shared by all analyses. Loaded on first use.
+    pub static ref ITEMS_REGISTRY: ConstantsRegistry<ItemsInfo> =
+        ConstantsRegistry::new("items", ItemsInfo::from_json);
+}
+
+/// Item data relevant for analysis. Key is the internal name used in purchase logs,
+/// e.g. "black_king_bar".
+#[derive(Clone, Debug)]
+pub struct Item {
+    pub key: String,
+    pub name: String,
+    pub cost: u64,
+}
+
+impl Item {
+    /// Placeholder of item missing in constants.
+    pub fn unknown(item_id: u64) -> Self {
+        Item {
+            key: format!("unknown_{}", item_id),
+            name: format!("Unknown item #{}", item_id),
+            cost: 0,
+        }
+    }
+}
+
+/// Contains maps ItemId -> Item and item key -> ItemId, read from given version of items constants.
+pub struct ItemsInfo {
+    items: HashMap<u64, Item>,
+    ids: HashMap<String, u64>,
+    version: i64,
+}
+
+impl ItemsInfo {
+    /// Initializes from items constants json, in format of
+    /// https://api.opendota.com/api/constants/items endpoint.
+    pub fn from_json(raw_items: &serde_json::Value, version: i64) -> Result<Self, BoxError> {
+        let items_constants = raw_items
+            .as_object()
+            .ok_or(serde_error::custom("Items constants is not json object."))?;
+        let mut items_info = ItemsInfo {
+            items: HashMap::new(),
+            ids: HashMap::new(),
+            version,
+        };
+        for (key, item) in items_constants {
+            let item_id = item["id"].as_u64().ok_or(serde_error::custom(format!(
+                "no field id for item: {}",
+                key
+            )))?;
+            let item_parsed = Item {
+                key: key.clone(),
+                name: item["dname"].as_str().unwrap_or(key.as_str()).to_string(),
+                cost: item["cost"].as_u64().unwrap_or_default(),
+            };
+            items_info.ids.insert(key.clone(), item_id);
+            items_info.items.insert(item_id, item_parsed);
+        }
+        Ok(items_info)
+    }
+
+    /// Returns None for items missing in constants.
+    pub fn get_item(&self, item_id: u64) -> Option<Item> {
+        self.items.get(&item_id).cloned()
+    }
+
+    /// Finds item by its key, as used in purchase logs.
+    pub fn get_item_by_key(&self, key: &str) -> Option<Item> {
+        self.ids
+            .get(key)
+            .and_then(|item_id| self.get_item(*item_id))
+    }
+
+    pub fn version(&self) -> i64 {
+        self.version
+    }
+}
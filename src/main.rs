@@ -3,8 +3,12 @@
 mod analyzers;
 mod data_retrieval;
 mod heroes_info;
+mod items_info;
+mod match_filter;
 mod match_stats;
+mod opendota_models;
 mod server;
+mod skill_bracket;
 mod storage;
 mod types;
 
@@ -29,6 +33,10 @@ lazy_static! {
         config
             .merge(config::File::with_name("config.json"))
             .unwrap();
+        // Zero rate would block every request forever.
+        if let Ok(rpm) = config.get_int("opendota_requests_per_minute") {
+            assert!(rpm > 0, "Field opendota_requests_per_minute must be positive.");
+        }
         config
     };
 }
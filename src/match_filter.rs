@@ -0,0 +1,71 @@
+use crate::match_stats::Match;
+use crate::types::GuildId;
+use crate::CONFIG;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Unranked and ranked lobbies.
+const DEFAULT_LOBBY_TYPES: [u64; 2] = [0, 7];
+/// All Pick, Captain's Mode and Ranked All Pick.
+const DEFAULT_GAME_MODES: [u64; 3] = [1, 2, 22];
+
+/// Specification of matches used by the analysis. Empty lists and missing bounds accept any value.
+/// Matches with unknown value of a restricted field are rejected.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MatchFilter {
+    #[serde(default)]
+    pub game_modes: Vec<u64>,
+    #[serde(default)]
+    pub lobby_types: Vec<u64>,
+    #[serde(default)]
+    pub since: Option<i64>,
+    #[serde(default)]
+    pub until: Option<i64>,
+    #[serde(default)]
+    pub patches: Vec<u64>,
+}
+
+impl Default for MatchFilter {
+    fn default() -> Self {
+        MatchFilter {
+            game_modes: DEFAULT_GAME_MODES.to_vec(),
+            lobby_types: DEFAULT_LOBBY_TYPES.to_vec(),
+            since: None,
+            until: None,
+            patches: vec![],
+        }
+    }
+}
+
+/// Checks whether value is on the list. Empty list accepts anything.
+fn is_allowed(allowed: &Vec<u64>, value: Option<u64>) -> bool {
+    allowed.is_empty() || value.map_or(false, |value| allowed.contains(&value))
+}
+
+impl MatchFilter {
+    /// Filter of a guild, set in "guild_match_filters" config field. Guilds without own filter
+    /// use "match_filter" config field, or ranked and unranked All Pick and Captain's Mode
+    /// matches if it's not set either.
+    pub fn for_guild(guild_id: &GuildId) -> Self {
+        let guild_filters: HashMap<GuildId, MatchFilter> =
+            CONFIG.get("guild_match_filters").unwrap_or_default();
+        match guild_filters.get(guild_id) {
+            Some(filter) => filter.clone(),
+            None => CONFIG.get("match_filter").unwrap_or_default(),
+        }
+    }
+
+    pub fn accepts(&self, match_: &Match) -> bool {
+        let match_stats = match_.get_match_stats();
+        let start_time = match_stats.get_start_time();
+        is_allowed(&self.game_modes, match_stats.get_game_mode())
+            && is_allowed(&self.lobby_types, match_stats.get_lobby_type())
+            && is_allowed(&self.patches, match_stats.get_patch())
+            && self
+                .since
+                .map_or(true, |since| start_time.map_or(false, |time| since <= time))
+            && self
+                .until
+                .map_or(true, |until| start_time.map_or(false, |time| time < until))
+    }
+}
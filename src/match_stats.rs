@@ -1,15 +1,26 @@
+use crate::opendota_models::{MatchDetails, MatchPlayer};
+use crate::types::AccountId;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::option::NoneError;
 
 /// Struct representing players stats at some match.
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct PlayerStats {
+    #[serde(default)]
+    account_id: Option<AccountId>,
     #[serde(default)]
     personaname: Option<String>,
     #[serde(default)]
     hero_id: Option<u64>,
     #[serde(default)]
     win: Option<u64>,
+    #[serde(default, rename = "isRadiant")]
+    is_radiant: Option<bool>,
+    #[serde(default)]
+    rank_tier: Option<u64>, // Medal * 10 + stars, at the time of the match
+    #[serde(default)]
+    leaver_status: Option<u64>, // 0 - stayed, 1 - disconnected safely, 2 and more - abandoned
     // kda stats
     #[serde(default)]
     kda: Option<f64>,
@@ -79,24 +90,337 @@ pub struct PlayerStats {
     rune_pickups: Option<u64>,
     #[serde(default)]
     roshan_kills: Option<u64>,
+    // items
+    #[serde(default)]
+    item_0: Option<u64>,
+    #[serde(default)]
+    item_1: Option<u64>,
+    #[serde(default)]
+    item_2: Option<u64>,
+    #[serde(default)]
+    item_3: Option<u64>,
+    #[serde(default)]
+    item_4: Option<u64>,
+    #[serde(default)]
+    item_5: Option<u64>,
+    #[serde(default)]
+    purchase_log: Option<Vec<Purchase>>, // Only in parsed matches
+}
+
+/// Single item purchase. Time is in seconds from the match start.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Purchase {
+    pub time: i64,
+    pub key: String,
+}
+
+/// Generates getters of numeric stats and their listing used by the performance analysis.
+macro_rules! numeric_stats {
+    ($($getter:ident => $field:ident: $type:ty),* $(,)?) => {
+        impl PlayerStats {
+            $(
+                pub fn $getter(&self) -> Option<$type> {
+                    self.$field
+                }
+            )*
+
+            /// All numeric stats by their field names, as f64.
+            pub fn get_numeric_stats(&self) -> Vec<(&'static str, Option<f64>)> {
+                vec![$((stringify!($field), self.$field.map(|value| value as f64))),*]
+            }
+        }
+    };
+}
+
+numeric_stats! {
+    get_kda => kda: f64,
+    get_kills => kills: u64,
+    get_deaths => deaths: u64,
+    get_assists => assists: u64,
+    get_gold_per_min => gold_per_min: u64,
+    get_xp_per_min => xp_per_min: u64,
+    get_level => level: u64,
+    get_lane_kills => lane_kills: u64,
+    get_last_hits => last_hits: u64,
+    get_denies => denies: u64,
+    get_neutral_kills => neutral_kills: u64,
+    get_lane_efficiency => lane_efficiency: f64,
+    get_hero_damage => hero_damage: u64,
+    get_hero_healing => hero_healing: u64,
+    get_stuns => stuns: f64,
+    get_camps_stacked => camps_stacked: u64,
+    get_creeps_stacked => creeps_stacked: u64,
+    get_obs_placed => obs_placed: u64,
+    get_sen_placed => sen_placed: u64,
+    get_observer_kills => observer_kills: u64,
+    get_sen_kills => sen_kills: u64,
+    get_tower_damage => tower_damage: u64,
+    get_tower_kills => tower_kills: u64,
+    get_purchase_tpscroll => purchase_tpscroll: u64,
+    get_buyback_count => buyback_count: u64,
+    get_courier_kills => courier_kills: u64,
+    get_rune_pickups => rune_pickups: u64,
+    get_roshan_kills => roshan_kills: u64,
+}
+
+impl PlayerStats {
+    /// Reads stats of the match player. Stats which aren't modelled by MatchPlayer are read
+    /// from its extras. Side is passed separately, as it may be inferred from the player slot.
+    pub fn from_player(player: &MatchPlayer, is_radiant: Option<bool>) -> serde_json::Result<Self> {
+        let mut stats: PlayerStats =
+            serde_json::from_value(serde_json::Value::Object(player.extras.clone()))?;
+        stats.account_id = player.account_id;
+        stats.hero_id = player.hero_id;
+        stats.win = player.win;
+        stats.is_radiant = is_radiant;
+        stats.leaver_status = player.leaver_status;
+        Ok(stats)
+    }
+
+    pub fn get_account_id(&self) -> Option<AccountId> {
+        self.account_id
+    }
+
+    pub fn get_personaname(&self) -> Option<&String> {
+        self.personaname.as_ref()
+    }
+
+    pub fn get_hero_id(&self) -> Option<u64> {
+        self.hero_id
+    }
+
+    pub fn get_win(&self) -> Option<u64> {
+        self.win
+    }
+
+    pub fn get_is_radiant(&self) -> Option<bool> {
+        self.is_radiant
+    }
+
+    pub fn get_rank_tier(&self) -> Option<u64> {
+        self.rank_tier
+    }
+
+    pub fn get_leaver_status(&self) -> Option<u64> {
+        self.leaver_status
+    }
+
+    pub fn get_lane(&self) -> Option<u64> {
+        self.lane
+    }
+
+    pub fn get_lane_role(&self) -> Option<u64> {
+        self.lane_role
+    }
+
+    pub fn get_is_roaming(&self) -> Option<bool> {
+        self.is_roaming
+    }
+
+    /// Item ids of final inventory, without empty slots.
+    pub fn get_inventory(&self) -> Vec<u64> {
+        vec![
+            self.item_0,
+            self.item_1,
+            self.item_2,
+            self.item_3,
+            self.item_4,
+            self.item_5,
+        ]
+        .into_iter()
+        .filter_map(|item| item)
+        .filter(|item_id| *item_id != 0)
+        .collect()
+    }
+
+    pub fn get_purchase_log(&self) -> Option<&Vec<Purchase>> {
+        self.purchase_log.as_ref()
+    }
 }
 
 /// Struct containing match global stats.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MatchStats {
     #[serde(default)]
     skill: Option<u64>, // Normal, High, Very High
+    #[serde(default)]
+    game_mode: Option<u64>,
+    #[serde(default)]
+    lobby_type: Option<u64>,
+    #[serde(default)]
+    start_time: Option<i64>,
+    #[serde(default)]
+    duration: Option<u64>, // In seconds
+    #[serde(default)]
+    radiant_win: Option<bool>,
+    #[serde(default)]
+    region: Option<u64>,
+    #[serde(default)]
+    cluster: Option<u64>,
+    #[serde(default)]
+    radiant_score: Option<u64>,
+    #[serde(default)]
+    dire_score: Option<u64>,
+    #[serde(default)]
+    patch: Option<u64>,
+}
+
+impl MatchStats {
+    /// Reads global stats of the match. Stats which aren't modelled by MatchDetails are read
+    /// from its extras.
+    pub fn from_details(match_details: &MatchDetails) -> serde_json::Result<Self> {
+        let mut stats: MatchStats =
+            serde_json::from_value(serde_json::Value::Object(match_details.extras.clone()))?;
+        stats.skill = match_details.skill;
+        stats.game_mode = match_details.game_mode;
+        stats.lobby_type = match_details.lobby_type;
+        stats.start_time = match_details.start_time;
+        stats.duration = match_details.duration;
+        stats.radiant_win = match_details.radiant_win;
+        stats.patch = match_details.patch;
+        Ok(stats)
+    }
+
+    pub fn get_skill(&self) -> Option<u64> {
+        self.skill
+    }
+
+    pub fn get_game_mode(&self) -> Option<u64> {
+        self.game_mode
+    }
+
+    pub fn get_lobby_type(&self) -> Option<u64> {
+        self.lobby_type
+    }
+
+    pub fn get_start_time(&self) -> Option<i64> {
+        self.start_time
+    }
+
+    pub fn get_duration(&self) -> Option<u64> {
+        self.duration
+    }
+
+    pub fn get_radiant_win(&self) -> Option<bool> {
+        self.radiant_win
+    }
+
+    pub fn get_region(&self) -> Option<u64> {
+        self.region
+    }
+
+    pub fn get_cluster(&self) -> Option<u64> {
+        self.cluster
+    }
+
+    pub fn get_radiant_score(&self) -> Option<u64> {
+        self.radiant_score
+    }
+
+    pub fn get_dire_score(&self) -> Option<u64> {
+        self.dire_score
+    }
+
+    pub fn get_patch(&self) -> Option<u64> {
+        self.patch
+    }
 }
 
-/// Parsed match data. Used as input to the analysisers.
-#[derive(Serialize, Deserialize, Debug)]
+/// Team of a non-member player, relative to certain members team.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Ally,
+    Enemy,
+}
+
+/// Hero played by a player who isn't counted as guild member in the match.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OtherHero {
+    hero_id: u64,
+    is_radiant: bool,
+}
+
+impl OtherHero {
+    pub fn new(hero_id: u64, is_radiant: bool) -> Self {
+        OtherHero {
+            hero_id,
+            is_radiant,
+        }
+    }
+
+    pub fn get_hero_id(&self) -> u64 {
+        self.hero_id
+    }
+
+    pub fn is_radiant(&self) -> bool {
+        self.is_radiant
+    }
+}
+
+/// Guild members playing on the same side of a match.
+#[derive(Debug, Clone)]
+pub struct Team {
+    players: Vec<AccountId>,
+    is_won: bool,
+    is_radiant: Option<bool>,
+}
+
+impl Team {
+    pub fn get_players(&self) -> &Vec<AccountId> {
+        &self.players
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.is_won
+    }
+
+    pub fn is_radiant(&self) -> Option<bool> {
+        self.is_radiant
+    }
+}
+
+/// Parsed match data. Used as input to the analysisers. Players stats contain only guild
+/// members, who may play on both sides. Heroes of the other players are kept in other heroes.
+/// Abandoned matches are ones with a leaver or which ended too early to be meaningful.
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Match {
     match_stats: MatchStats,
     players_stats: Vec<PlayerStats>,
+    #[serde(default)]
+    other_heroes: Vec<OtherHero>,
+    #[serde(default)]
+    abandoned: bool,
 }
 
 pub type PlayerName = String;
 
+/// Display names of guild members, resolved from their latest profiles.
+/// Analyzers identify players by account id, names are used only in results.
+#[derive(Debug, Default)]
+pub struct MemberNames {
+    names: HashMap<AccountId, PlayerName>,
+}
+
+impl MemberNames {
+    pub fn new(names: HashMap<AccountId, PlayerName>) -> Self {
+        MemberNames { names }
+    }
+
+    /// Returns member name, or account id if member profile is unknown.
+    pub fn get(&self, account_id: &AccountId) -> PlayerName {
+        match self.names.get(account_id) {
+            Some(name) => name.clone(),
+            None => account_id.to_string(),
+        }
+    }
+}
+
+/// Guild matches prepared for the analysis.
+pub struct GuildMatches {
+    pub matches: Vec<Match>,
+    pub member_names: MemberNames,
+}
+
 #[derive(Debug)]
 pub enum StatsError {
     MissingField(NoneError),
@@ -113,35 +437,160 @@ pub type StatsResult<T> = std::result::Result<T, StatsError>;
 
 /// As match data may be missing some fields, getters return StatsResult.
 impl Match {
-    pub fn new(match_stats: MatchStats, players_stats: Vec<PlayerStats>) -> Match {
+    pub fn new(
+        match_stats: MatchStats,
+        players_stats: Vec<PlayerStats>,
+        other_heroes: Vec<OtherHero>,
+        abandoned: bool,
+    ) -> Match {
         Match {
             match_stats,
             players_stats,
+            other_heroes,
+            abandoned,
         }
     }
 
-    pub fn is_won(&self) -> StatsResult<bool> {
-        Ok(self.players_stats[0].win? == 1)
+    pub fn is_abandoned(&self) -> bool {
+        self.abandoned
     }
 
-    pub fn get_team(&self) -> StatsResult<Vec<PlayerName>> {
-        let mut team = vec![];
+    /// Members grouped by the side they played on, winning team first. There are two teams
+    /// only if members played against each other. Result of each side is taken from the
+    /// match result, or from the player result if the match result is unknown.
+    pub fn get_teams(&self) -> StatsResult<Vec<Team>> {
+        let mut teams: Vec<Team> = vec![];
         for p in self.players_stats.iter() {
-            team.push(p.personaname.clone()?); //((p.personaname.clone()?, p.lane?));
+            let account_id = p.account_id?;
+            let is_radiant = p.is_radiant?;
+            let is_won = match self.match_stats.radiant_win {
+                Some(radiant_win) => radiant_win == is_radiant,
+                None => p.win? == 1,
+            };
+            match teams
+                .iter_mut()
+                .find(|team| team.is_radiant == Some(is_radiant))
+            {
+                Some(team) => team.players.push(account_id),
+                None => teams.push(Team {
+                    players: vec![account_id],
+                    is_won,
+                    is_radiant: Some(is_radiant),
+                }),
+            }
         }
-        Ok(team)
+        teams.sort_by_key(|team| !team.is_won);
+        Ok(teams)
     }
 
-    pub fn get_player_hero(&self, player_name: &PlayerName) -> StatsResult<u64> {
+    /// Kills of the team and of its enemies.
+    pub fn get_score(&self, team: &Team) -> StatsResult<(u64, u64)> {
+        let radiant_score = self.match_stats.radiant_score?;
+        let dire_score = self.match_stats.dire_score?;
+        if team.is_radiant? {
+            Ok((radiant_score, dire_score))
+        } else {
+            Ok((dire_score, radiant_score))
+        }
+    }
+
+    pub fn get_player_hero(&self, account_id: &AccountId) -> StatsResult<u64> {
         for p in self.players_stats.iter() {
-            if p.personaname.as_ref()? == player_name {
+            if p.account_id? == *account_id {
                 return Ok(p.hero_id?);
             }
         }
         Err(StatsError::NoTargetPlayer())
     }
 
+    pub fn get_player_stats(&self, account_id: &AccountId) -> StatsResult<&PlayerStats> {
+        for p in self.players_stats.iter() {
+            if p.account_id? == *account_id {
+                return Ok(p);
+            }
+        }
+        Err(StatsError::NoTargetPlayer())
+    }
+
+    pub fn get_other_heroes(&self) -> &Vec<OtherHero> {
+        &self.other_heroes
+    }
+
+    /// Ids of non-member heroes on the given side relative to the team. Empty if side of
+    /// the team is unknown.
+    pub fn get_other_heroes_ids(&self, team: &Team, side: Side) -> Vec<u64> {
+        let team_radiant = match team.is_radiant {
+            Some(team_radiant) => team_radiant,
+            None => return vec![],
+        };
+        self.other_heroes
+            .iter()
+            .filter(|hero| (hero.is_radiant == team_radiant) == (side == Side::Ally))
+            .map(|hero| hero.hero_id)
+            .collect()
+    }
+
+    /// Rank tiers of the members who had one at the time of the match.
+    pub fn get_members_rank_tiers(&self) -> Vec<u64> {
+        self.players_stats
+            .iter()
+            .filter_map(|p| p.rank_tier)
+            .collect()
+    }
+
+    pub fn get_match_stats(&self) -> &MatchStats {
+        &self.match_stats
+    }
+
     pub fn get_team_size(&self) -> usize {
         self.players_stats.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn versus_match(radiant_win: Option<bool>) -> Match {
+        let match_stats: MatchStats = serde_json::from_value(json!({
+            "radiant_win": radiant_win,
+            "radiant_score": 30,
+            "dire_score": 20,
+        }))
+        .unwrap();
+        // Player results disagree with the match result, as in the malformed api responses.
+        let players_stats = vec![
+            json!({"account_id": 1, "isRadiant": true, "win": 0}),
+            json!({"account_id": 2, "isRadiant": false, "win": 0}),
+            json!({"account_id": 3, "isRadiant": true, "win": 1}),
+        ]
+        .into_iter()
+        .map(|player| serde_json::from_value(player).unwrap())
+        .collect();
+        Match::new(match_stats, players_stats, vec![], false)
+    }
+
+    #[test]
+    fn teams_are_grouped_by_side() {
+        let match_ = versus_match(Some(true));
+        let teams = match_.get_teams().unwrap();
+        assert_eq!(teams.len(), 2);
+        assert_eq!(teams[0].get_players(), &vec![1, 3]);
+        assert_eq!(teams[0].is_radiant(), Some(true));
+        assert!(teams[0].is_won());
+        assert_eq!(teams[1].get_players(), &vec![2]);
+        assert!(!teams[1].is_won());
+        assert_eq!(match_.get_score(&teams[0]).unwrap(), (30, 20));
+        assert_eq!(match_.get_score(&teams[1]).unwrap(), (20, 30));
+    }
+
+    #[test]
+    fn team_result_falls_back_to_player_result() {
+        let match_ = versus_match(None);
+        let teams = match_.get_teams().unwrap();
+        assert_eq!(teams[0].get_players(), &vec![1, 3]);
+        assert!(!teams[0].is_won());
+        assert!(!teams[1].is_won());
+    }
+}
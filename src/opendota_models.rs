@@ -0,0 +1,250 @@
+use crate::types::{AccountId, MatchId};
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// Response fields which aren't modelled explicitly. They are kept, so no data is lost on storing.
+pub type Extras = Map<String, Value>;
+
+/// Error of reading opendota response, pointing to the field which couldn't be read.
+#[derive(Debug)]
+pub struct ModelError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ModelError {
+    /// Prepends parent field to the error path.
+    fn within(self, parent: &str) -> Self {
+        let path = if self.path.is_empty() {
+            parent.to_string()
+        } else if self.path.starts_with('[') {
+            format!("{}{}", parent, self.path)
+        } else {
+            format!("{}.{}", parent, self.path)
+        };
+        ModelError {
+            path,
+            message: self.message,
+        }
+    }
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid field `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// Models read from json object, with unknown fields moved to extras.
+pub trait FromObject: Sized {
+    fn from_object(object: Extras) -> Result<Self, ModelError>;
+}
+
+/// Removes field from the object and reads it. Missing field is read as null.
+fn take_field<T: DeserializeOwned>(object: &mut Extras, field: &str) -> Result<T, ModelError> {
+    let value = object.remove(field).unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| ModelError {
+        path: field.to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Removes array field from the object and reads its elements as models.
+fn take_models<T: FromObject>(object: &mut Extras, field: &str) -> Result<Vec<T>, ModelError> {
+    let value = object.remove(field).unwrap_or(Value::Null);
+    from_json_array(value).map_err(|e| e.within(field))
+}
+
+/// Reads model from json value.
+pub fn from_json<T: FromObject>(value: Value) -> Result<T, ModelError> {
+    match value {
+        Value::Object(object) => T::from_object(object),
+        other => Err(ModelError {
+            path: String::new(),
+            message: format!("expected object, found: {}", other),
+        }),
+    }
+}
+
+/// Reads json array of models. Errors point to the index of invalid element.
+pub fn from_json_array<T: FromObject>(value: Value) -> Result<Vec<T>, ModelError> {
+    match value {
+        Value::Array(elements) => elements
+            .into_iter()
+            .enumerate()
+            .map(|(i, element)| from_json(element).map_err(|e| e.within(&format!("[{}]", i))))
+            .collect(),
+        other => Err(ModelError {
+            path: String::new(),
+            message: format!("expected array, found: {}", other),
+        }),
+    }
+}
+
+/// Implements Deserialize via FromObject, so field errors are reported by serde too.
+macro_rules! deserialize_from_object {
+    ($model:ty) => {
+        impl<'de> Deserialize<'de> for $model {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let object = Extras::deserialize(deserializer)?;
+                Self::from_object(object).map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+/// Steam profile of a player, part of /players/{account_id} response.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlayerProfile {
+    pub account_id: AccountId,
+    pub personaname: Option<String>,
+    pub avatarfull: Option<String>,
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl FromObject for PlayerProfile {
+    fn from_object(mut object: Extras) -> Result<Self, ModelError> {
+        Ok(PlayerProfile {
+            account_id: take_field(&mut object, "account_id")?,
+            personaname: take_field(&mut object, "personaname")?,
+            avatarfull: take_field(&mut object, "avatarfull")?,
+            extras: object,
+        })
+    }
+}
+
+deserialize_from_object!(PlayerProfile);
+
+/// Response of /players/{account_id} endpoint. Profile is missing for unknown accounts.
+#[derive(Serialize, Debug, Clone)]
+pub struct PlayerInfo {
+    pub profile: Option<PlayerProfile>,
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl FromObject for PlayerInfo {
+    fn from_object(mut object: Extras) -> Result<Self, ModelError> {
+        let profile = match object.remove("profile") {
+            None | Some(Value::Null) => None,
+            Some(profile) => Some(from_json(profile).map_err(|e| e.within("profile"))?),
+        };
+        Ok(PlayerInfo {
+            profile,
+            extras: object,
+        })
+    }
+}
+
+deserialize_from_object!(PlayerInfo);
+
+/// Match found in player history, element of /players/{account_id}/matches response.
+/// Used for discovery of new matches.
+#[derive(Serialize, Debug, Clone)]
+pub struct MatchSummary {
+    pub match_id: MatchId,
+    pub start_time: i64,
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl MatchSummary {
+    pub fn new(match_id: MatchId, start_time: i64) -> Self {
+        MatchSummary {
+            match_id,
+            start_time,
+            extras: Extras::new(),
+        }
+    }
+}
+
+impl FromObject for MatchSummary {
+    fn from_object(mut object: Extras) -> Result<Self, ModelError> {
+        Ok(MatchSummary {
+            match_id: take_field(&mut object, "match_id")?,
+            start_time: take_field::<Option<i64>>(&mut object, "start_time")?.unwrap_or_default(),
+            extras: object,
+        })
+    }
+}
+
+deserialize_from_object!(MatchSummary);
+
+/// Player of a match, part of /matches/{match_id} response. Account id is missing for
+/// anonymous players.
+#[derive(Serialize, Debug, Clone)]
+pub struct MatchPlayer {
+    pub account_id: Option<AccountId>,
+    pub player_slot: Option<u64>, // Less than 128 for radiant players
+    #[serde(rename = "isRadiant")]
+    pub is_radiant: Option<bool>,
+    pub hero_id: Option<u64>,
+    pub win: Option<u64>,
+    pub leaver_status: Option<u64>, // 0 - stayed, 1 - disconnected safely, 2 and more - abandoned
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl FromObject for MatchPlayer {
+    fn from_object(mut object: Extras) -> Result<Self, ModelError> {
+        Ok(MatchPlayer {
+            account_id: take_field(&mut object, "account_id")?,
+            player_slot: take_field(&mut object, "player_slot")?,
+            is_radiant: take_field(&mut object, "isRadiant")?,
+            hero_id: take_field(&mut object, "hero_id")?,
+            win: take_field(&mut object, "win")?,
+            leaver_status: take_field(&mut object, "leaver_status")?,
+            extras: object,
+        })
+    }
+}
+
+deserialize_from_object!(MatchPlayer);
+
+/// Response of /matches/{match_id} endpoint. Replay version is present only in parsed matches.
+#[derive(Serialize, Debug, Clone)]
+pub struct MatchDetails {
+    pub match_id: MatchId,
+    pub start_time: Option<i64>,
+    pub duration: Option<u64>, // In seconds
+    pub radiant_win: Option<bool>,
+    pub game_mode: Option<u64>,
+    pub lobby_type: Option<u64>,
+    pub patch: Option<u64>,
+    pub skill: Option<u64>, // Normal, High, Very High
+    pub version: Option<u64>,
+    pub players: Vec<MatchPlayer>,
+    #[serde(flatten)]
+    pub extras: Extras,
+}
+
+impl MatchDetails {
+    pub fn is_parsed(&self) -> bool {
+        self.version.is_some()
+    }
+}
+
+impl FromObject for MatchDetails {
+    fn from_object(mut object: Extras) -> Result<Self, ModelError> {
+        Ok(MatchDetails {
+            match_id: take_field(&mut object, "match_id")?,
+            start_time: take_field(&mut object, "start_time")?,
+            duration: take_field(&mut object, "duration")?,
+            radiant_win: take_field(&mut object, "radiant_win")?,
+            game_mode: take_field(&mut object, "game_mode")?,
+            lobby_type: take_field(&mut object, "lobby_type")?,
+            patch: take_field(&mut object, "patch")?,
+            skill: take_field(&mut object, "skill")?,
+            version: take_field(&mut object, "version")?,
+            players: take_models(&mut object, "players")?,
+            extras: object,
+        })
+    }
+}
+
+deserialize_from_object!(MatchDetails);
@@ -1,11 +1,23 @@
+use crate::analyzers::analyzers_utils::{count_excluded_matches, find_unknown_heroes};
 use crate::analyzers::heroes::{get_hero_players_stats, get_heroes_played};
-use crate::analyzers::players::get_players_wr;
+use crate::analyzers::items::get_item_builds;
+use crate::analyzers::matchups::get_hero_matchups;
+use crate::analyzers::overview::get_guild_overview;
+use crate::analyzers::performance::get_players_performance;
+use crate::analyzers::players::{get_members_versus, get_players_wr};
+use crate::analyzers::positions::get_positions_wr;
 use crate::analyzers::roles::{
     compress_roles_wr, get_roles_records, get_roles_synergies, get_roles_wr,
 };
 use crate::data_retrieval::retrieval_agent::process_guild_matches_retrieval;
-use crate::match_stats::Match;
-use crate::storage::result_storage::AnalysisTag;
+use crate::heroes_info::{HeroesInfo, HEROES_REGISTRY};
+use crate::items_info::{ItemsInfo, ITEMS_REGISTRY};
+use crate::match_stats::{GuildMatches, Match, MemberNames};
+use crate::skill_bracket::{
+    average_rank_tier, bracket_matches, split_by_skill_bracket, SKILL_BRACKETS,
+};
+use crate::storage::processing_report_storage::ProcessingReport;
+use crate::storage::result_storage::{AnalysisTag, ResultMetadata};
 use crate::storage::Storage;
 use crate::BoxError;
 use std::{collections::VecDeque, sync::Arc};
@@ -20,23 +32,70 @@ async fn process_roles_wr(
     storage: Arc<Storage>,
     guild_id: &String,
     matches: &Vec<Match>,
+    member_names: &MemberNames,
+    heroes_info: &HeroesInfo,
+    metadata: &ResultMetadata,
 ) -> Result<(), BoxError> {
-    let roles_wr = get_roles_wr(&matches);
-    let roles_synergy = get_roles_synergies(&roles_wr);
-    let roles_records = get_roles_records(&roles_wr);
+    let roles_wr = get_roles_wr(&matches, heroes_info);
+    let roles_synergy = get_roles_synergies(&roles_wr, member_names);
+    let roles_records = get_roles_records(&roles_wr, member_names);
 
-    let roles_wr = compress_roles_wr(roles_wr);
+    let roles_wr = compress_roles_wr(roles_wr, member_names);
     let roles_wr_json = serde_json::to_value(roles_wr)?;
     let roles_synergy_json = serde_json::to_value(roles_synergy)?;
     let roles_records_json = serde_json::to_value(roles_records)?;
     storage
-        .store_result(guild_id, roles_wr_json, AnalysisTag::RolesWr)
+        .store_result(guild_id, roles_wr_json, AnalysisTag::RolesWr, metadata)
         .await?;
     storage
-        .store_result(guild_id, roles_synergy_json, AnalysisTag::RolesSynergy)
+        .store_result(
+            guild_id,
+            roles_synergy_json,
+            AnalysisTag::RolesSynergy,
+            metadata,
+        )
         .await?;
     storage
-        .store_result(guild_id, roles_records_json, AnalysisTag::RolesRecords)
+        .store_result(
+            guild_id,
+            roles_records_json,
+            AnalysisTag::RolesRecords,
+            metadata,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Computes and stores positions based results. Positions setups are evaluated same way as
+/// roles setups.
+async fn process_positions_wr(
+    storage: Arc<Storage>,
+    guild_id: &String,
+    matches: &Vec<Match>,
+    member_names: &MemberNames,
+    metadata: &ResultMetadata,
+) -> Result<(), BoxError> {
+    let positions_wr = get_positions_wr(matches);
+    let positions_synergy = get_roles_synergies(&positions_wr, member_names);
+
+    let positions_wr = compress_roles_wr(positions_wr, member_names);
+    let positions_wr_json = serde_json::to_value(positions_wr)?;
+    let positions_synergy_json = serde_json::to_value(positions_synergy)?;
+    storage
+        .store_result(
+            guild_id,
+            positions_wr_json,
+            AnalysisTag::PositionsWr,
+            metadata,
+        )
+        .await?;
+    storage
+        .store_result(
+            guild_id,
+            positions_synergy_json,
+            AnalysisTag::PositionsSynergy,
+            metadata,
+        )
         .await?;
     Ok(())
 }
@@ -46,15 +105,72 @@ async fn process_heroes_data(
     storage: Arc<Storage>,
     guild_id: &String,
     matches: &Vec<Match>,
+    member_names: &MemberNames,
+    heroes_info: &HeroesInfo,
+    metadata: &ResultMetadata,
 ) -> Result<(), BoxError> {
-    let heroes_played = get_heroes_played(&matches);
-    let heroes_players_stats = get_hero_players_stats(&heroes_played);
+    let heroes_played = get_heroes_played(&matches, heroes_info);
+    let heroes_players_stats = get_hero_players_stats(&heroes_played, member_names);
+    let hero_matchups = get_hero_matchups(matches, heroes_info);
     let heroes_players_stats_json = serde_json::to_value(heroes_players_stats)?;
+    let hero_matchups_json = serde_json::to_value(hero_matchups)?;
     storage
         .store_result(
             guild_id,
             heroes_players_stats_json,
             AnalysisTag::HeroesPlayersStats,
+            metadata,
+        )
+        .await?;
+    storage
+        .store_result(
+            guild_id,
+            hero_matchups_json,
+            AnalysisTag::HeroMatchups,
+            metadata,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Computes and stores items based results.
+async fn process_items_data(
+    storage: Arc<Storage>,
+    guild_id: &String,
+    matches: &Vec<Match>,
+    member_names: &MemberNames,
+    heroes_info: &HeroesInfo,
+    items_info: &ItemsInfo,
+    metadata: &ResultMetadata,
+) -> Result<(), BoxError> {
+    let item_builds = get_item_builds(matches, heroes_info, items_info, member_names);
+    let item_builds_json = serde_json::to_value(item_builds)?;
+    storage
+        .store_result(
+            guild_id,
+            item_builds_json,
+            AnalysisTag::ItemBuilds,
+            metadata,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Computes and stores guild level results.
+async fn process_overview_data(
+    storage: Arc<Storage>,
+    guild_id: &String,
+    matches: &Vec<Match>,
+    metadata: &ResultMetadata,
+) -> Result<(), BoxError> {
+    let guild_overview = get_guild_overview(matches);
+    let guild_overview_json = serde_json::to_value(guild_overview)?;
+    storage
+        .store_result(
+            guild_id,
+            guild_overview_json,
+            AnalysisTag::GuildOverview,
+            metadata,
         )
         .await?;
     Ok(())
@@ -65,21 +181,164 @@ async fn process_players_data(
     storage: Arc<Storage>,
     guild_id: &String,
     matches: &Vec<Match>,
+    member_names: &MemberNames,
+    heroes_info: &HeroesInfo,
+    metadata: &ResultMetadata,
 ) -> Result<(), BoxError> {
-    let players_wr = get_players_wr(matches);
+    let players_wr = get_players_wr(matches, member_names);
+    let players_performance = get_players_performance(matches, heroes_info, member_names);
+    let members_versus = get_members_versus(matches, member_names);
     let players_wr_json = serde_json::to_value(players_wr)?;
+    let players_performance_json = serde_json::to_value(players_performance)?;
+    let members_versus_json = serde_json::to_value(members_versus)?;
     storage
-        .store_result(guild_id, players_wr_json, AnalysisTag::PlayersWr)
+        .store_result(guild_id, players_wr_json, AnalysisTag::PlayersWr, metadata)
         .await?;
+    storage
+        .store_result(
+            guild_id,
+            players_performance_json,
+            AnalysisTag::PlayerPerformance,
+            metadata,
+        )
+        .await?;
+    storage
+        .store_result(
+            guild_id,
+            members_versus_json,
+            AnalysisTag::MembersVersus,
+            metadata,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Computes and stores all results on given matches.
+async fn process_matches(
+    storage: Arc<Storage>,
+    guild_id: &String,
+    matches: &Vec<Match>,
+    member_names: &MemberNames,
+    heroes_info: &HeroesInfo,
+    items_info: &ItemsInfo,
+    metadata: &ResultMetadata,
+) -> Result<(), BoxError> {
+    process_roles_wr(
+        storage.clone(),
+        &guild_id,
+        &matches,
+        &member_names,
+        &heroes_info,
+        &metadata,
+    )
+    .await?;
+    process_positions_wr(
+        storage.clone(),
+        &guild_id,
+        &matches,
+        &member_names,
+        &metadata,
+    )
+    .await?;
+    process_heroes_data(
+        storage.clone(),
+        &guild_id,
+        &matches,
+        &member_names,
+        &heroes_info,
+        &metadata,
+    )
+    .await?;
+    process_items_data(
+        storage.clone(),
+        &guild_id,
+        &matches,
+        &member_names,
+        &heroes_info,
+        &items_info,
+        &metadata,
+    )
+    .await?;
+    process_overview_data(storage.clone(), &guild_id, &matches, &metadata).await?;
+    process_players_data(
+        storage.clone(),
+        &guild_id,
+        &matches,
+        &member_names,
+        &heroes_info,
+        &metadata,
+    )
+    .await?;
     Ok(())
 }
 
-/// Computes results for specified guild.
+/// Computes results for specified guild. If "split_by_skill_bracket" is set, results are
+/// also computed separately on matches of each skill bracket.
 async fn process_guild_data(storage: Arc<Storage>, guild_id: &String) -> Result<(), BoxError> {
-    let matches = process_guild_matches_retrieval(storage.clone(), guild_id).await?;
-    process_roles_wr(storage.clone(), &guild_id, &matches).await?;
-    process_heroes_data(storage.clone(), &guild_id, &matches).await?;
-    process_players_data(storage.clone(), &guild_id, &matches).await?;
+    let GuildMatches {
+        matches,
+        member_names,
+    } = process_guild_matches_retrieval(storage.clone(), guild_id).await?;
+    let heroes_info = HEROES_REGISTRY.get(&storage).await?;
+    let items_info = ITEMS_REGISTRY.get(&storage).await?;
+    let metadata = ResultMetadata {
+        excluded_matches: count_excluded_matches(&matches),
+        constants_version: Some(heroes_info.version()),
+        items_constants_version: Some(items_info.version()),
+        bracket: None,
+        average_rank_tier: average_rank_tier(&matches),
+    };
+    info!("Excluded {} abandoned matches.", metadata.excluded_matches);
+    let mut report = ProcessingReport::new(guild_id);
+    report.matches = matches.len() as u32;
+    report.excluded_matches = metadata.excluded_matches;
+    report.constants_version = metadata.constants_version;
+    report.unknown_hero_ids = find_unknown_heroes(&heroes_info, &matches);
+    if !report.unknown_hero_ids.is_empty() {
+        warn!(
+            "Heroes {:?} are missing in constants version {}.",
+            report.unknown_hero_ids,
+            heroes_info.version()
+        );
+    }
+    storage.store_processing_report(&report).await?;
+    process_matches(
+        storage.clone(),
+        guild_id,
+        &matches,
+        &member_names,
+        &heroes_info,
+        &items_info,
+        &metadata,
+    )
+    .await?;
+    if !split_by_skill_bracket() {
+        return Ok(());
+    }
+    for bracket in SKILL_BRACKETS.iter() {
+        let matches = bracket_matches(&matches, bracket);
+        info!(
+            "Processing {} matches of {} bracket.",
+            matches.len(),
+            bracket
+        );
+        let metadata = ResultMetadata {
+            excluded_matches: count_excluded_matches(&matches),
+            bracket: Some(bracket.to_string()),
+            average_rank_tier: average_rank_tier(&matches),
+            ..metadata.clone()
+        };
+        process_matches(
+            storage.clone(),
+            guild_id,
+            &matches,
+            &member_names,
+            &heroes_info,
+            &items_info,
+            &metadata,
+        )
+        .await?;
+    }
     Ok(())
 }
 
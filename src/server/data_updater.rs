@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use crate::heroes_info::HEROES_REGISTRY;
+use crate::items_info::ITEMS_REGISTRY;
 use crate::server::data_processing;
 use crate::storage::{result_storage::ResultsState, Storage};
 use crate::types::GuildId;
@@ -42,13 +44,29 @@ async fn check_if_update(
     Ok(())
 }
 
-/// Spawns updater which checks if guild results need update periodically.
+/// Spawns updater which checks if guild results need update periodically. Heroes and items
+/// constants are refreshed every "constants_refresh_hours".
 pub async fn spawn_worker(
     data_processing_queue: data_processing::DPQ,
     storage: Arc<Storage>,
 ) -> Result<JoinHandle<()>, BoxError> {
+    let refresh_hours = CONFIG.get_int("constants_refresh_hours").unwrap_or(24);
     Ok(tokio::spawn(async move {
+        let mut last_constants_refresh = 0;
         loop {
+            if Utc::now().timestamp() - last_constants_refresh > refresh_hours * 3600 {
+                match HEROES_REGISTRY.refresh(&storage).await {
+                    Ok(heroes_info) => {
+                        info!("Heroes constants version: {}", heroes_info.version());
+                        last_constants_refresh = Utc::now().timestamp();
+                    }
+                    Err(e) => error!("Unable to refresh heroes constants: {}", e),
+                }
+                match ITEMS_REGISTRY.refresh(&storage).await {
+                    Ok(items_info) => info!("Items constants version: {}", items_info.version()),
+                    Err(e) => error!("Unable to refresh items constants: {}", e),
+                }
+            }
             match check_if_update(storage.clone(), data_processing_queue.clone()).await {
                 Ok(()) => (),
                 Err(e) => {
@@ -0,0 +1,84 @@
+use crate::storage::{player_groups_storage::PlayerGroup, Storage};
+use crate::types::PlayerId;
+use rocket::response::content;
+use rocket::State;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Body of group create and update requests. Members are steam account ids.
+#[derive(Deserialize)]
+struct GroupRequest {
+    name: String,
+    members: Vec<PlayerId>,
+}
+
+/// Validates group request and stores group. Returns stored group as json.
+async fn save_group(
+    body: String,
+    group_id: Option<String>,
+    storage: &Storage,
+) -> Option<content::Json<String>> {
+    let request: GroupRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("Invalid group request: {}", e);
+            return None;
+        }
+    };
+    let mut group = match PlayerGroup::new(request.name, request.members) {
+        Ok(group) => group,
+        Err(e) => {
+            warn!("Invalid group: {}", e);
+            return None;
+        }
+    };
+    if let Some(group_id) = group_id {
+        group.group_id = group_id;
+    }
+    match storage.store_player_group(&group).await {
+        Ok(()) => Some(content::Json(serde_json::to_string(&group).ok()?)),
+        Err(e) => {
+            warn!("Error during storing group {}: {}", group.group_id, e);
+            None
+        }
+    }
+}
+
+/// Creates user defined group of players. Returned group_id can be used in place of
+/// guild_id in every /guild/... endpoint.
+#[post("/group", data = "<body>")]
+pub async fn create_group<'a>(
+    body: String,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    save_group(body, None, &storage).await
+}
+
+/// Replaces name and members of existing group.
+#[post("/group/<group_id>", data = "<body>")]
+pub async fn update_group<'a>(
+    group_id: String,
+    body: String,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    if let Err(e) = storage.get_player_group(&group_id).await {
+        warn!("Unable to update group: {}", e);
+        return None;
+    }
+    save_group(body, Some(group_id), &storage).await
+}
+
+/// Get user defined group.
+#[get("/group/<group_id>")]
+pub async fn get_group<'a>(
+    group_id: String,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage.get_player_group(&group_id).await {
+        Ok(group) => Some(content::Json(serde_json::to_string(&group).ok()?)),
+        Err(e) => {
+            warn!("Error during the reading of group: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use crate::data_retrieval::retrieval_agent;
+use crate::storage::Storage;
+use rocket::response::content;
+use rocket::State;
+use std::sync::Arc;
+
+/// Get guild metadata: name, tag, logo, creation date and member count.
+#[get("/guild/info/<guild_id>")]
+pub async fn guild_info<'a>(
+    guild_id: String,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage.get_guild_info(&guild_id).await {
+        Ok(guild_info) => Some(content::Json(serde_json::to_string(&guild_info).ok()?)),
+        Err(e) => {
+            warn!("Error during the reading of guild info: {}", e);
+            None
+        }
+    }
+}
+
+/// Search guilds by tag or name. Returns matching guilds metadata.
+#[get("/guild/search/<query>")]
+pub async fn search_guilds<'a>(
+    query: String,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match retrieval_agent::search_guilds(storage.inner().clone(), &query).await {
+        Ok(guilds) => Some(content::Json(serde_json::to_string(&guilds).ok()?)),
+        Err(e) => {
+            warn!("Error during guild search: {}", e);
+            None
+        }
+    }
+}
+
+/// Get guild roster history: join and leave dates of current and former members.
+#[get("/guild/roster_history/<guild_id>")]
+pub async fn roster_history<'a>(
+    guild_id: String,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage.get_roster_history(&guild_id).await {
+        Ok(tenures) => Some(content::Json(serde_json::to_string(&tenures).ok()?)),
+        Err(e) => {
+            warn!("Error during the reading of roster history: {}", e);
+            None
+        }
+    }
+}
+
+/// Get report of the last guild processing, e.g. heroes missing in constants.
+#[get("/guild/processing_report/<guild_id>")]
+pub async fn processing_report<'a>(
+    guild_id: String,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage.get_processing_report(&guild_id).await {
+        Ok(report) => Some(content::Json(serde_json::to_string(&report).ok()?)),
+        Err(e) => {
+            warn!("Error during the reading of processing report: {}", e);
+            None
+        }
+    }
+}
@@ -1,4 +1,6 @@
 mod data_processing;
 mod data_updater;
+mod group_routes;
+mod guild_info_routes;
 mod health_routes;
 pub mod server;
@@ -1,5 +1,9 @@
 use crate::server::data_processing::{self, DPQ};
 use crate::server::data_updater;
+use crate::server::group_routes::{create_group, get_group, update_group};
+use crate::server::guild_info_routes::{
+    guild_info, processing_report, roster_history, search_guilds,
+};
 use crate::server::health_routes::{health, start, stop};
 use crate::storage::{
     result_storage::{AnalysisTag, GuildResultsState, ResultsState},
@@ -13,12 +17,16 @@ use std::{collections::VecDeque, sync::Arc};
 use tokio::sync::RwLock;
 
 /// Get roles winratio results.
-#[get("/guild/roles_wr/<guild_id>")]
+#[get("/guild/roles_wr/<guild_id>?<bracket>")]
 async fn roles_wr_req<'a>(
     guild_id: String,
+    bracket: Option<String>,
     storage: State<'a, Arc<Storage>>,
 ) -> Option<content::Json<String>> {
-    match storage.get_result(&guild_id, AnalysisTag::RolesWr).await {
+    match storage
+        .get_result(&guild_id, AnalysisTag::RolesWr, &bracket)
+        .await
+    {
         Ok(payload) => Some(content::Json(payload)),
         Err(e) => {
             warn!("Error during the reading of roles_synergy result: {}", e);
@@ -28,13 +36,14 @@ async fn roles_wr_req<'a>(
 }
 
 /// Get roles synergy results.
-#[get("/guild/roles_synergy/<guild_id>")]
+#[get("/guild/roles_synergy/<guild_id>?<bracket>")]
 async fn roles_synergy_req<'a>(
     guild_id: String,
+    bracket: Option<String>,
     storage: State<'a, Arc<Storage>>,
 ) -> Option<content::Json<String>> {
     match storage
-        .get_result(&guild_id, AnalysisTag::RolesSynergy)
+        .get_result(&guild_id, AnalysisTag::RolesSynergy, &bracket)
         .await
     {
         Ok(payload) => Some(content::Json(payload)),
@@ -46,13 +55,14 @@ async fn roles_synergy_req<'a>(
 }
 
 /// Get roles records results.
-#[get("/guild/roles_records/<guild_id>")]
+#[get("/guild/roles_records/<guild_id>?<bracket>")]
 async fn roles_records_req<'a>(
     guild_id: String,
+    bracket: Option<String>,
     storage: State<'a, Arc<Storage>>,
 ) -> Option<content::Json<String>> {
     match storage
-        .get_result(&guild_id, AnalysisTag::RolesRecords)
+        .get_result(&guild_id, AnalysisTag::RolesRecords, &bracket)
         .await
     {
         Ok(payload) => Some(content::Json(payload)),
@@ -63,14 +73,56 @@ async fn roles_records_req<'a>(
     }
 }
 
+/// Get positions winratio results.
+#[get("/guild/positions_wr/<guild_id>?<bracket>")]
+async fn positions_wr_req<'a>(
+    guild_id: String,
+    bracket: Option<String>,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage
+        .get_result(&guild_id, AnalysisTag::PositionsWr, &bracket)
+        .await
+    {
+        Ok(payload) => Some(content::Json(payload)),
+        Err(e) => {
+            warn!("Error during the reading of positions_wr result: {}", e);
+            None
+        }
+    }
+}
+
+/// Get positions synergy results.
+#[get("/guild/positions_synergy/<guild_id>?<bracket>")]
+async fn positions_synergy_req<'a>(
+    guild_id: String,
+    bracket: Option<String>,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage
+        .get_result(&guild_id, AnalysisTag::PositionsSynergy, &bracket)
+        .await
+    {
+        Ok(payload) => Some(content::Json(payload)),
+        Err(e) => {
+            warn!(
+                "Error during the reading of positions_synergy result: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
 /// Get heroes players stats results.
-#[get("/guild/heroes_players_stats/<guild_id>")]
+#[get("/guild/heroes_players_stats/<guild_id>?<bracket>")]
 async fn heroes_players_stats_req<'a>(
     guild_id: String,
+    bracket: Option<String>,
     storage: State<'a, Arc<Storage>>,
 ) -> Option<content::Json<String>> {
     match storage
-        .get_result(&guild_id, AnalysisTag::HeroesPlayersStats)
+        .get_result(&guild_id, AnalysisTag::HeroesPlayersStats, &bracket)
         .await
     {
         Ok(payload) => Some(content::Json(payload)),
@@ -84,13 +136,36 @@ async fn heroes_players_stats_req<'a>(
     }
 }
 
+/// Get hero matchups results.
+#[get("/guild/hero_matchups/<guild_id>?<bracket>")]
+async fn hero_matchups_req<'a>(
+    guild_id: String,
+    bracket: Option<String>,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage
+        .get_result(&guild_id, AnalysisTag::HeroMatchups, &bracket)
+        .await
+    {
+        Ok(payload) => Some(content::Json(payload)),
+        Err(e) => {
+            warn!("Error during the reading of hero_matchups result: {}", e);
+            None
+        }
+    }
+}
+
 /// Get players winratio results.
-#[get("/guild/players_wr/<guild_id>")]
+#[get("/guild/players_wr/<guild_id>?<bracket>")]
 async fn players_wr_req<'a>(
     guild_id: String,
+    bracket: Option<String>,
     storage: State<'a, Arc<Storage>>,
 ) -> Option<content::Json<String>> {
-    match storage.get_result(&guild_id, AnalysisTag::PlayersWr).await {
+    match storage
+        .get_result(&guild_id, AnalysisTag::PlayersWr, &bracket)
+        .await
+    {
         Ok(payload) => Some(content::Json(payload)),
         Err(e) => {
             warn!("Error during the reading of players_wr result: {}", e);
@@ -99,6 +174,85 @@ async fn players_wr_req<'a>(
     }
 }
 
+/// Get item builds results.
+#[get("/guild/item_builds/<guild_id>?<bracket>")]
+async fn item_builds_req<'a>(
+    guild_id: String,
+    bracket: Option<String>,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage
+        .get_result(&guild_id, AnalysisTag::ItemBuilds, &bracket)
+        .await
+    {
+        Ok(payload) => Some(content::Json(payload)),
+        Err(e) => {
+            warn!("Error during the reading of item_builds result: {}", e);
+            None
+        }
+    }
+}
+
+/// Get players performance results.
+#[get("/guild/player_performance/<guild_id>?<bracket>")]
+async fn player_performance_req<'a>(
+    guild_id: String,
+    bracket: Option<String>,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage
+        .get_result(&guild_id, AnalysisTag::PlayerPerformance, &bracket)
+        .await
+    {
+        Ok(payload) => Some(content::Json(payload)),
+        Err(e) => {
+            warn!(
+                "Error during the reading of player_performance result: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Get guild overview results.
+#[get("/guild/overview/<guild_id>?<bracket>")]
+async fn guild_overview_req<'a>(
+    guild_id: String,
+    bracket: Option<String>,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage
+        .get_result(&guild_id, AnalysisTag::GuildOverview, &bracket)
+        .await
+    {
+        Ok(payload) => Some(content::Json(payload)),
+        Err(e) => {
+            warn!("Error during the reading of guild_overview result: {}", e);
+            None
+        }
+    }
+}
+
+/// Get members versus members results.
+#[get("/guild/members_versus/<guild_id>?<bracket>")]
+async fn members_versus_req<'a>(
+    guild_id: String,
+    bracket: Option<String>,
+    storage: State<'a, Arc<Storage>>,
+) -> Option<content::Json<String>> {
+    match storage
+        .get_result(&guild_id, AnalysisTag::MembersVersus, &bracket)
+        .await
+    {
+        Ok(payload) => Some(content::Json(payload)),
+        Err(e) => {
+            warn!("Error during the reading of members_versus result: {}", e);
+            None
+        }
+    }
+}
+
 /// Request to process guild. First use for specific guild subscribes it to
 /// be processed by the app.
 #[post("/guild/process/<guild_id>")]
@@ -155,7 +309,21 @@ pub async fn run() -> Result<(), BoxError> {
                 roles_records_req,
                 heroes_players_stats_req,
                 players_wr_req,
+                item_builds_req,
+                player_performance_req,
+                guild_overview_req,
+                members_versus_req,
+                positions_wr_req,
+                positions_synergy_req,
+                hero_matchups_req,
                 process_guild,
+                guild_info,
+                search_guilds,
+                roster_history,
+                processing_report,
+                create_group,
+                update_group,
+                get_group,
                 start,
                 stop,
                 health
@@ -0,0 +1,60 @@
+use crate::match_stats::Match;
+use crate::CONFIG;
+
+/// Skill brackets in order of opendota skill field values (1 - Normal, 2 - High, 3 - Very High).
+pub const SKILL_BRACKETS: [&str; 3] = ["normal", "high", "very_high"];
+
+/// Whether results are also computed separately for each skill bracket, set by
+/// "split_by_skill_bracket" config field.
+pub fn split_by_skill_bracket() -> bool {
+    CONFIG.get_bool("split_by_skill_bracket").unwrap_or(false)
+}
+
+/// Bracket of the rank medal (rank tier divided by 10). Herald to Archon is Normal skill,
+/// Legend is High, Ancient and above is Very High.
+fn medal_bracket(medal: u64) -> Option<&'static str> {
+    match medal {
+        1..=4 => Some(SKILL_BRACKETS[0]),
+        5 => Some(SKILL_BRACKETS[1]),
+        6..=8 => Some(SKILL_BRACKETS[2]),
+        _ => None,
+    }
+}
+
+/// Skill bracket of the match. If opendota didn't assign skill to the match, it's based on
+/// average rank of the members at the time.
+pub fn match_bracket(match_: &Match) -> Option<&'static str> {
+    match match_.get_match_stats().get_skill() {
+        Some(skill @ 1..=3) => Some(SKILL_BRACKETS[skill as usize - 1]),
+        _ => {
+            let rank_tiers = match_.get_members_rank_tiers();
+            if rank_tiers.is_empty() {
+                return None;
+            }
+            let average_rank_tier = rank_tiers.iter().sum::<u64>() / rank_tiers.len() as u64;
+            medal_bracket(average_rank_tier / 10)
+        }
+    }
+}
+
+/// Matches of given skill bracket.
+pub fn bracket_matches(matches: &Vec<Match>, bracket: &str) -> Vec<Match> {
+    matches
+        .iter()
+        .filter(|match_| match_bracket(match_) == Some(bracket))
+        .cloned()
+        .collect()
+}
+
+/// Average rank tier of members at the time of the matches, rounded to one decimal place.
+pub fn average_rank_tier(matches: &Vec<Match>) -> Option<f64> {
+    let rank_tiers: Vec<u64> = matches
+        .iter()
+        .flat_map(|match_| match_.get_members_rank_tiers())
+        .collect();
+    if rank_tiers.is_empty() {
+        return None;
+    }
+    let average = rank_tiers.iter().sum::<u64>() as f64 / rank_tiers.len() as f64;
+    Some((average * 10.).round() / 10.)
+}
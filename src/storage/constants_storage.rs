@@ -0,0 +1,57 @@
+use crate::storage::Storage;
+use crate::BoxError;
+use chrono::Utc;
+use mongodb::options::FindOneOptions;
+use mongodb::{
+    self,
+    bson::{self, doc},
+};
+use serde::{Deserialize, Serialize};
+
+/// Version of game constants of some kind (e.g. heroes). Data is json in raw string.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StoredConstants {
+    pub kind: String,
+    pub version: i64,
+    pub updated_at: i64,
+    pub data: String,
+}
+
+impl Storage {
+    /// Retrieves the newest version of constants from dotastats/constants collection.
+    /// Returns None if no constants of that kind are stored yet.
+    pub async fn get_constants(&self, kind: &str) -> Result<Option<StoredConstants>, BoxError> {
+        let coll = self.db_client.collection("constants");
+        let options = FindOneOptions::builder().sort(doc! {"version": -1}).build();
+        match coll.find_one(doc! {"kind": kind}, options).await? {
+            Some(constants_doc) => Ok(Some(bson::from_bson(constants_doc.into())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores constants as a new version, unless they are the same as the newest one.
+    /// Returns the newest version.
+    pub async fn store_constants(
+        &self,
+        kind: &str,
+        data: &serde_json::Value,
+    ) -> Result<StoredConstants, BoxError> {
+        let data = data.to_string();
+        let version = match self.get_constants(kind).await? {
+            Some(current) if current.data == data => return Ok(current),
+            Some(current) => current.version + 1,
+            None => 1,
+        };
+        let constants = StoredConstants {
+            kind: kind.to_string(),
+            version,
+            updated_at: Utc::now().timestamp(),
+            data,
+        };
+        let coll = self.db_client.collection("constants");
+        coll.insert_one(bson::to_document(&constants)?, None)
+            .await?;
+        info!("Stored {} constants version {}.", kind, version);
+        Ok(constants)
+    }
+}
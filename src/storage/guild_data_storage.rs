@@ -1,3 +1,4 @@
+use crate::opendota_models::MatchDetails;
 use crate::storage::Storage;
 use crate::types::GuildId;
 use crate::CONFIG;
@@ -5,7 +6,7 @@ use mongodb::{
     self,
     bson::{self, doc, Bson, Document},
 };
-use serde::{de::Error, Deserialize, Serialize};
+use serde::{Deserialize, Serialize};
 use tokio::stream::StreamExt;
 
 /// Struct containing match information and index, stored in mongodb.
@@ -16,18 +17,10 @@ pub struct MatchData {
 }
 
 impl MatchData {
-    pub fn from_json(match_data: &serde_json::Value) -> serde_json::error::Result<MatchData> {
-        let match_id = match match_data["match_id"].as_i64() {
-            Some(id) => id,
-            None => {
-                return Err(serde_json::error::Error::custom(
-                    "No match_id in provided match_data.",
-                ))
-            }
-        };
+    pub fn from_details(match_data: &MatchDetails) -> serde_json::error::Result<MatchData> {
         Ok(MatchData {
-            match_id,
-            info: match_data.to_string(),
+            match_id: match_data.match_id as i64,
+            info: serde_json::to_string(match_data)?,
         })
     }
 }
@@ -40,10 +33,10 @@ struct GuildDataBatch {
 }
 
 /// Extracts match info from bson. Used for parsing the database match data output.
-fn extract_match_data_from_doc(match_bson: &mut Bson) -> Option<serde_json::Value> {
+fn extract_match_data_from_doc(match_bson: &mut Bson) -> Option<MatchDetails> {
     let match_json: serde_json::Value = match_bson.clone().into();
     match serde_json::from_str(match_json["info"].as_str()?) {
-        Ok(json) => Some(json),
+        Ok(match_details) => Some(match_details),
         Err(e) => {
             warn!("Can't parse match data: {}", e);
             None
@@ -56,18 +49,18 @@ impl Storage {
     pub async fn get_guild_data(
         &self,
         guild_id: &GuildId,
-    ) -> mongodb::error::Result<Vec<serde_json::Value>> {
+    ) -> mongodb::error::Result<Vec<MatchDetails>> {
         let coll = self.db_client.collection("guild_data");
         let filter = doc! {"guild_id": guild_id};
         let mut cursor = coll.find(filter, None).await?;
         let mut res = vec![];
         while let Some(guild_batch_doc) = cursor.next().await {
             for match_bsons in guild_batch_doc?.get_array_mut("matches") {
-                let mut match_json_batch: Vec<serde_json::Value> = match_bsons
+                let mut match_batch: Vec<MatchDetails> = match_bsons
                     .into_iter()
                     .filter_map(extract_match_data_from_doc)
                     .collect();
-                res.append(&mut match_json_batch);
+                res.append(&mut match_batch);
             }
         }
         Ok(res)
@@ -77,23 +70,25 @@ impl Storage {
     pub async fn add_guild_data(
         &self,
         guild_id: &GuildId,
-        match_data: &Vec<serde_json::Value>,
+        match_data: &Vec<MatchDetails>,
     ) -> mongodb::error::Result<()> {
         if match_data.is_empty() {
             return Ok(());
         }
         let match_docs: Vec<MatchData> = match_data
             .iter()
-            .filter_map(|match_json| match MatchData::from_json(match_json) {
-                Ok(res) => Some(res),
-                Err(e) => {
-                    error!(
-                        "Unable to prepare match json to save to the database: {}",
-                        e
-                    );
-                    None
-                }
-            })
+            .filter_map(
+                |match_details| match MatchData::from_details(match_details) {
+                    Ok(res) => Some(res),
+                    Err(e) => {
+                        error!(
+                            "Unable to prepare match json to save to the database: {}",
+                            e
+                        );
+                        None
+                    }
+                },
+            )
             .collect();
         let chunk_size = CONFIG
             .get_int("db_guild_data_chunk_size")
@@ -119,4 +114,27 @@ impl Storage {
         coll.insert_many(guild_data_batches, None).await?;
         Ok(())
     }
+
+    /// Replaces data of already stored guild match, e.g. once it has been parsed.
+    pub async fn replace_guild_match(
+        &self,
+        guild_id: &GuildId,
+        match_data: &MatchDetails,
+    ) -> mongodb::error::Result<()> {
+        let match_doc = match MatchData::from_details(match_data) {
+            Ok(res) => res,
+            Err(e) => {
+                error!(
+                    "Unable to prepare match json to replace in the database: {}",
+                    e
+                );
+                return Ok(());
+            }
+        };
+        let coll = self.db_client.collection("guild_data");
+        let filter = doc! {"guild_id": guild_id, "matches.match_id": match_doc.match_id};
+        let update = doc! {"$set": {"matches.$.info": match_doc.info}};
+        coll.update_one(filter, update, None).await?;
+        Ok(())
+    }
 }
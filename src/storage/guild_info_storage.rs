@@ -0,0 +1,41 @@
+use crate::storage::Storage;
+use crate::types::GuildId;
+use crate::BoxError;
+use mongodb::{
+    self,
+    bson::{self, doc},
+};
+use serde::{Deserialize, Serialize};
+
+/// Guild metadata, shown by the client next to the stats.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GuildInfo {
+    pub guild_id: GuildId,
+    pub name: String,
+    pub tag: String,
+    pub logo_url: Option<String>,
+    pub created: Option<i64>,
+    pub member_count: u32,
+}
+
+impl Storage {
+    /// Retrieves guild metadata from dotastats/guild_info collection.
+    pub async fn get_guild_info(&self, guild_id: &GuildId) -> Result<GuildInfo, BoxError> {
+        let coll = self.db_client.collection("guild_info");
+        let info_doc = coll
+            .find_one(doc! {"guild_id": guild_id}, None)
+            .await?
+            .ok_or("Guild info not found.")?;
+        Ok(bson::from_bson(info_doc.into())?)
+    }
+
+    /// Stores guild metadata, replacing previous one.
+    pub async fn store_guild_info(&self, guild_info: &GuildInfo) -> mongodb::error::Result<()> {
+        let coll = self.db_client.collection("guild_info");
+        coll.delete_one(doc! {"guild_id": &guild_info.guild_id}, None)
+            .await?;
+        coll.insert_one(bson::to_document(guild_info)?, None)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use crate::storage::Storage;
+use crate::types::{GuildId, PlayerId};
+use chrono::Utc;
+use mongodb::{
+    self,
+    bson::{self, doc},
+    options::FindOneOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tokio::stream::StreamExt;
+
+/// Guild members seen during single retrieval run.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MembershipSnapshot {
+    pub guild_id: GuildId,
+    pub members: Vec<PlayerId>,
+    pub timestamp: i64,
+}
+
+/// Continuous period of membership. Joined is unknown for members present in the first snapshot,
+/// left is unknown for current members.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MemberTenure {
+    pub player_id: PlayerId,
+    pub joined: Option<i64>,
+    pub left: Option<i64>,
+}
+
+impl MemberTenure {
+    /// Checks whether given time is within the tenure.
+    pub fn contains(&self, timestamp: i64) -> bool {
+        self.joined.map_or(true, |joined| joined <= timestamp)
+            && self.left.map_or(true, |left| timestamp < left)
+    }
+}
+
+/// Derives members tenures from snapshots sorted by timestamp. Member who left and joined again
+/// has multiple tenures.
+pub fn derive_tenures(snapshots: &Vec<MembershipSnapshot>) -> Vec<MemberTenure> {
+    let mut tenures = vec![];
+    let mut current: HashMap<PlayerId, Option<i64>> = HashMap::new();
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        let joined = if i == 0 {
+            None
+        } else {
+            Some(snapshot.timestamp)
+        };
+        for member in snapshot.members.iter() {
+            current.entry(member.clone()).or_insert(joined);
+        }
+        let left: Vec<PlayerId> = current
+            .keys()
+            .filter(|member| !snapshot.members.contains(member))
+            .cloned()
+            .collect();
+        for member in left {
+            let joined = current.remove(&member).unwrap_or(None);
+            tenures.push(MemberTenure {
+                player_id: member,
+                joined,
+                left: Some(snapshot.timestamp),
+            });
+        }
+    }
+    tenures.extend(current.into_iter().map(|(member, joined)| MemberTenure {
+        player_id: member,
+        joined,
+        left: None,
+    }));
+    tenures.sort_by(|a, b| (&a.player_id, a.joined).cmp(&(&b.player_id, b.joined)));
+    tenures
+}
+
+impl Storage {
+    /// Retrieves membership snapshots of a guild from dotastats/guild_membership collection,
+    /// sorted by timestamp.
+    pub async fn get_membership_snapshots(
+        &self,
+        guild_id: &GuildId,
+    ) -> mongodb::error::Result<Vec<MembershipSnapshot>> {
+        let coll = self.db_client.collection("guild_membership");
+        let mut cursor = coll.find(doc! {"guild_id": guild_id}, None).await?;
+        let mut res: Vec<MembershipSnapshot> = vec![];
+        while let Some(snapshot_doc) = cursor.next().await {
+            res.push(bson::from_bson(snapshot_doc?.into())?);
+        }
+        res.sort_by_key(|snapshot| snapshot.timestamp);
+        Ok(res)
+    }
+
+    /// Stores current guild members, if they differ from the members of the last snapshot.
+    pub async fn add_membership_snapshot(
+        &self,
+        guild_id: &GuildId,
+        members: &Vec<PlayerId>,
+    ) -> mongodb::error::Result<()> {
+        let coll = self.db_client.collection("guild_membership");
+        let options = FindOneOptions::builder()
+            .sort(doc! {"timestamp": -1})
+            .build();
+        if let Some(last_doc) = coll.find_one(doc! {"guild_id": guild_id}, options).await? {
+            let last: MembershipSnapshot = bson::from_bson(last_doc.into())?;
+            let last_members: HashSet<&PlayerId> = last.members.iter().collect();
+            if last_members == members.iter().collect() {
+                return Ok(());
+            }
+        }
+        let snapshot = MembershipSnapshot {
+            guild_id: guild_id.clone(),
+            members: members.clone(),
+            timestamp: Utc::now().timestamp(),
+        };
+        coll.insert_one(bson::to_document(&snapshot)?, None).await?;
+        Ok(())
+    }
+
+    /// Derives guild members tenures from all stored snapshots.
+    pub async fn get_roster_history(
+        &self,
+        guild_id: &GuildId,
+    ) -> mongodb::error::Result<Vec<MemberTenure>> {
+        Ok(derive_tenures(
+            &self.get_membership_snapshots(guild_id).await?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(members: &[&str], timestamp: i64) -> MembershipSnapshot {
+        MembershipSnapshot {
+            guild_id: "1".to_string(),
+            members: members.iter().map(|member| member.to_string()).collect(),
+            timestamp,
+        }
+    }
+
+    fn periods(tenures: &Vec<MemberTenure>, player_id: &str) -> Vec<(Option<i64>, Option<i64>)> {
+        tenures
+            .iter()
+            .filter(|tenure| tenure.player_id == player_id)
+            .map(|tenure| (tenure.joined, tenure.left))
+            .collect()
+    }
+
+    #[test]
+    fn first_snapshot_members_have_unknown_join() {
+        let tenures = derive_tenures(&vec![snapshot(&["a"], 10), snapshot(&["a"], 20)]);
+        assert_eq!(periods(&tenures, "a"), vec![(None, None)]);
+    }
+
+    #[test]
+    fn member_joins_later() {
+        let tenures = derive_tenures(&vec![snapshot(&["a"], 10), snapshot(&["a", "b"], 20)]);
+        assert_eq!(periods(&tenures, "b"), vec![(Some(20), None)]);
+        assert!(!tenures[1].contains(19));
+        assert!(tenures[1].contains(20));
+    }
+
+    #[test]
+    fn member_leaves() {
+        let tenures = derive_tenures(&vec![snapshot(&["a", "b"], 10), snapshot(&["a"], 20)]);
+        assert_eq!(periods(&tenures, "b"), vec![(None, Some(20))]);
+        assert!(tenures[1].contains(19));
+        assert!(!tenures[1].contains(20));
+    }
+
+    #[test]
+    fn member_rejoins_with_new_tenure() {
+        let tenures = derive_tenures(&vec![
+            snapshot(&["a", "b"], 10),
+            snapshot(&["a"], 20),
+            snapshot(&["a", "b"], 30),
+        ]);
+        assert_eq!(
+            periods(&tenures, "b"),
+            vec![(None, Some(20)), (Some(30), None)]
+        );
+        assert_eq!(periods(&tenures, "a"), vec![(None, None)]);
+    }
+}
@@ -1,5 +1,14 @@
+pub mod constants_storage;
 pub mod guild_data_storage;
+pub mod guild_info_storage;
+pub mod guild_membership_storage;
+pub mod parse_requests_storage;
+pub mod player_groups_storage;
+pub mod player_info_storage;
+pub mod processing_report_storage;
 pub mod result_storage;
+pub mod skipped_matches_storage;
+pub mod sync_state_storage;
 use crate::BoxError;
 use crate::CONFIG;
 use mongodb::bson::doc;
@@ -43,15 +52,27 @@ impl Storage {
                 )
             }
         };
-        let client = mongodb::Client::with_uri_str(uri.as_str()).await?;
+        let storage = Storage::connect(&uri, "dotastats").await?;
         info!(
             "Succesfully connected with {}:{} database.",
             db_host, db_port
         );
+        Ok(storage)
+    }
+
+    /// Connects to the given database of mongodb instance.
+    pub async fn connect(uri: &str, database: &str) -> mongodb::error::Result<Storage> {
+        let client = mongodb::Client::with_uri_str(uri).await?;
         Ok(Storage {
-            db_client: client.database("dotastats"),
+            db_client: client.database(database),
         })
     }
+
+    /// Drops whole database. Used to clean up after tests.
+    #[cfg(test)]
+    pub async fn drop_database(&self) -> mongodb::error::Result<()> {
+        self.db_client.drop(None).await
+    }
 }
 
 fn get_credentials() -> Result<UserCredentials, BoxError> {
@@ -0,0 +1,64 @@
+use crate::storage::Storage;
+use crate::types::{GuildId, MatchId};
+use chrono::Utc;
+use mongodb::{
+    self,
+    bson::{self, doc},
+};
+use serde::{Deserialize, Serialize};
+use tokio::stream::StreamExt;
+
+/// Match submitted to be parsed by opendota, which should be re-fetched once parsed.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ParseRequest {
+    pub guild_id: GuildId,
+    pub match_id: i64,
+    pub requested_at: i64,
+}
+
+impl Storage {
+    /// Retrieves pending parse requests of a guild from dotastats/parse_requests collection.
+    pub async fn get_parse_requests(
+        &self,
+        guild_id: &GuildId,
+    ) -> mongodb::error::Result<Vec<ParseRequest>> {
+        let coll = self.db_client.collection("parse_requests");
+        let mut cursor = coll.find(doc! {"guild_id": guild_id}, None).await?;
+        let mut res = vec![];
+        while let Some(request_doc) = cursor.next().await {
+            res.push(bson::from_bson(request_doc?.into())?);
+        }
+        Ok(res)
+    }
+
+    /// Adds pending parse request of a guild match.
+    pub async fn add_parse_request(
+        &self,
+        guild_id: &GuildId,
+        match_id: &MatchId,
+    ) -> mongodb::error::Result<()> {
+        let request = ParseRequest {
+            guild_id: guild_id.clone(),
+            match_id: *match_id as i64,
+            requested_at: Utc::now().timestamp(),
+        };
+        let coll = self.db_client.collection("parse_requests");
+        coll.insert_one(bson::to_document(&request)?, None).await?;
+        Ok(())
+    }
+
+    /// Removes parse request, either fulfilled or expired.
+    pub async fn remove_parse_request(
+        &self,
+        guild_id: &GuildId,
+        match_id: &MatchId,
+    ) -> mongodb::error::Result<()> {
+        let coll = self.db_client.collection("parse_requests");
+        coll.delete_one(
+            doc! {"guild_id": guild_id, "match_id": *match_id as i64},
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+}
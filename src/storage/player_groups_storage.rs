@@ -0,0 +1,67 @@
+use crate::storage::Storage;
+use crate::types::{GuildId, PlayerId};
+use crate::BoxError;
+use mongodb::{
+    self,
+    bson::{self, doc, oid::ObjectId},
+};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of user defined groups ids, which distinguishes them from stratz guild ids.
+pub const GROUP_ID_PREFIX: &str = "group_";
+
+/// Checks whether id points to user defined group instead of stratz guild.
+pub fn is_group_id(guild_id: &GuildId) -> bool {
+    guild_id.starts_with(GROUP_ID_PREFIX)
+}
+
+/// User defined group of players, analysed the same way as stratz guilds.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PlayerGroup {
+    pub group_id: GuildId,
+    pub name: String,
+    pub members: Vec<PlayerId>,
+}
+
+impl PlayerGroup {
+    /// Creates group with new unique id. Members have to be steam account ids.
+    pub fn new(name: String, members: Vec<PlayerId>) -> Result<Self, BoxError> {
+        if name.trim().is_empty() {
+            return Err("Group name is empty.".into());
+        }
+        if members.is_empty() {
+            return Err("Group has no members.".into());
+        }
+        for member in members.iter() {
+            member
+                .parse::<u64>()
+                .map_err(|_| format!("Invalid steam account id: {}", member))?;
+        }
+        Ok(PlayerGroup {
+            group_id: format!("{}{}", GROUP_ID_PREFIX, ObjectId::new().to_hex()),
+            name,
+            members,
+        })
+    }
+}
+
+impl Storage {
+    /// Retrieves group from dotastats/player_groups collection.
+    pub async fn get_player_group(&self, group_id: &GuildId) -> Result<PlayerGroup, BoxError> {
+        let coll = self.db_client.collection("player_groups");
+        let group_doc = coll
+            .find_one(doc! {"group_id": group_id}, None)
+            .await?
+            .ok_or(format!("Group {} not found.", group_id))?;
+        Ok(bson::from_bson(group_doc.into())?)
+    }
+
+    /// Stores group in dotastats/player_groups collection, replacing group with the same id.
+    pub async fn store_player_group(&self, group: &PlayerGroup) -> mongodb::error::Result<()> {
+        let coll = self.db_client.collection("player_groups");
+        coll.delete_one(doc! {"group_id": &group.group_id}, None)
+            .await?;
+        coll.insert_one(bson::to_document(group)?, None).await?;
+        Ok(())
+    }
+}
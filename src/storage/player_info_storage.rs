@@ -0,0 +1,35 @@
+use crate::opendota_models::PlayerInfo;
+use crate::storage::Storage;
+use crate::types::PlayerId;
+use crate::BoxError;
+use mongodb::options::UpdateOptions;
+use mongodb::{self, bson::doc};
+
+impl Storage {
+    /// Retrieves player profile from dotastats/player_info collection, if it was stored.
+    pub async fn get_player_info(
+        &self,
+        player_id: &PlayerId,
+    ) -> Result<Option<PlayerInfo>, BoxError> {
+        let coll = self.db_client.collection("player_info");
+        match coll.find_one(doc! {"player_id": player_id}, None).await? {
+            Some(info_doc) => Ok(Some(serde_json::from_str(info_doc.get_str("info")?)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores player profile as {player_id, info}, where info is opendota json. Replaces
+    /// previous profile of the player.
+    pub async fn store_player_info(
+        &self,
+        player_id: &PlayerId,
+        player_info: &PlayerInfo,
+    ) -> Result<(), BoxError> {
+        let coll = self.db_client.collection("player_info");
+        let update = doc! {"$set": {"info": serde_json::to_string(player_info)?}};
+        let options = UpdateOptions::builder().upsert(true).build();
+        coll.update_one(doc! {"player_id": player_id}, update, options)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::storage::Storage;
+use crate::types::GuildId;
+use crate::BoxError;
+use chrono::Utc;
+use mongodb::{
+    self,
+    bson::{self, doc},
+};
+use serde::{Deserialize, Serialize};
+
+/// Summary of the last guild processing. Shows issues which don't stop the processing,
+/// e.g. heroes missing in stale constants.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ProcessingReport {
+    pub guild_id: GuildId,
+    pub timestamp: i64,
+    pub matches: u32,
+    pub excluded_matches: u32,
+    pub constants_version: Option<i64>,
+    pub unknown_hero_ids: Vec<u64>,
+}
+
+impl ProcessingReport {
+    pub fn new(guild_id: &GuildId) -> Self {
+        ProcessingReport {
+            guild_id: guild_id.clone(),
+            timestamp: Utc::now().timestamp(),
+            matches: 0,
+            excluded_matches: 0,
+            constants_version: None,
+            unknown_hero_ids: vec![],
+        }
+    }
+}
+
+impl Storage {
+    /// Retrieves the last processing report of a guild from dotastats/processing_reports collection.
+    pub async fn get_processing_report(
+        &self,
+        guild_id: &GuildId,
+    ) -> Result<ProcessingReport, BoxError> {
+        let coll = self.db_client.collection("processing_reports");
+        let report_doc = coll
+            .find_one(doc! {"guild_id": guild_id}, None)
+            .await?
+            .ok_or("Processing report not found.")?;
+        Ok(bson::from_bson(report_doc.into())?)
+    }
+
+    /// Stores processing report, replacing the previous one.
+    pub async fn store_processing_report(
+        &self,
+        report: &ProcessingReport,
+    ) -> mongodb::error::Result<()> {
+        let coll = self.db_client.collection("processing_reports");
+        coll.delete_one(doc! {"guild_id": &report.guild_id}, None)
+            .await?;
+        coll.insert_one(bson::to_document(report)?, None).await?;
+        Ok(())
+    }
+}
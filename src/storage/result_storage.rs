@@ -22,6 +22,13 @@ pub enum AnalysisTag {
     RolesRecords,
     HeroesPlayersStats,
     PlayersWr,
+    ItemBuilds,
+    PlayerPerformance,
+    GuildOverview,
+    MembersVersus,
+    PositionsWr,
+    PositionsSynergy,
+    HeroMatchups,
 }
 
 impl fmt::Display for AnalysisTag {
@@ -30,6 +37,26 @@ impl fmt::Display for AnalysisTag {
     }
 }
 
+/// Information about the analysis input, returned alongside the result.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResultMetadata {
+    /// Abandoned matches left out of the analysis.
+    #[serde(default)]
+    pub excluded_matches: u32,
+    /// Version of heroes constants used by the analysis.
+    #[serde(default)]
+    pub constants_version: Option<i64>,
+    /// Version of items constants used by the analysis.
+    #[serde(default)]
+    pub items_constants_version: Option<i64>,
+    /// Skill bracket of the analysed matches, all matches if missing.
+    #[serde(default)]
+    pub bracket: Option<String>,
+    /// Average rank tier of members at the time of the analysed matches.
+    #[serde(default)]
+    pub average_rank_tier: Option<f64>,
+}
+
 /// Analysis results stored in database. Payload is json in raw string.
 #[derive(Serialize, Deserialize)]
 struct StoredResult {
@@ -37,6 +64,8 @@ struct StoredResult {
     timestamp: i64,
     tag: String,
     payload: String,
+    #[serde(default)]
+    metadata: ResultMetadata,
 }
 
 /// Analysis result in format to be sent to client. Payload is parsed.
@@ -44,6 +73,7 @@ struct StoredResult {
 struct ResultToReturn {
     guild_id: String,
     timestamp: i64,
+    metadata: ResultMetadata,
     payload: serde_json::Value,
 }
 
@@ -88,7 +118,8 @@ fn extract_results_state(
 }
 
 impl Storage {
-    /// Returns state of calculated analysis results for specified guild.
+    /// Returns state of calculated analysis results for specified guild. Only results computed
+    /// on all matches are taken into account, as skill bracket results may be no longer updated.
     pub async fn get_guild_results_state(
         &self,
         guild_id: &GuildId,
@@ -97,7 +128,8 @@ impl Storage {
         let options = FindOptions::builder()
             .projection(doc! {"tag": 1, "timestamp": 1})
             .build();
-        let mut cursor = coll.find(doc! {"guild_id": guild_id}, options).await?;
+        let filter = doc! {"guild_id": guild_id, "metadata.bracket": bson::Bson::Null};
+        let mut cursor = coll.find(filter, options).await?;
         let mut tags_found = vec![];
         let mut timestamps = vec![];
         while let Some(result_doc) = cursor.next().await {
@@ -108,13 +140,16 @@ impl Storage {
         extract_results_state(guild_id, &tags_found, &timestamps)
     }
 
-    /// Returns states of analysis results for each guild. Used by updater.
+    /// Returns states of analysis results for each guild, ignoring skill bracket results.
+    /// Used by updater.
     pub async fn get_guilds_results_state(&self) -> Result<Vec<GuildResultsState>, BoxError> {
         let coll = self.db_client.collection("analysis_results");
         let options = FindOptions::builder()
             .projection(doc! {"guild_id": 1, "tag": 1, "timestamp": 1})
             .build();
-        let mut cursor = coll.find(doc! {}, options).await?;
+        let mut cursor = coll
+            .find(doc! {"metadata.bracket": bson::Bson::Null}, options)
+            .await?;
         let mut tags_found: HashMap<GuildId, Vec<String>> = HashMap::new();
         let mut timestamps: HashMap<GuildId, Vec<i64>> = HashMap::new();
         let mut processed_guilds: HashSet<GuildId> = HashSet::new();
@@ -139,35 +174,48 @@ impl Storage {
             .collect()
     }
 
-    /// Stores single analysis result in the database.
+    /// Stores single analysis result in the database. Results of each skill bracket,
+    /// set in metadata, are stored separately.
     pub async fn store_result(
         &self,
         guild_id: &GuildId,
         payload: serde_json::Value,
         analysis_tag: AnalysisTag,
+        metadata: &ResultMetadata,
     ) -> mongodb::error::Result<()> {
         let res = StoredResult {
             guild_id: guild_id.clone(),
             timestamp: Utc::now().timestamp(),
             tag: analysis_tag.to_string(),
             payload: payload.to_string(),
+            metadata: metadata.clone(),
         };
         let result_doc = bson::to_document(&res)?;
         let coll = self.db_client.collection("analysis_results");
-        let filter = doc! {"guild_id": guild_id, "tag": analysis_tag.to_string()};
+        let filter = doc! {
+            "guild_id": guild_id,
+            "tag": analysis_tag.to_string(),
+            "metadata.bracket": metadata.bracket.clone(),
+        };
         coll.delete_one(filter, None).await?;
         coll.insert_one(result_doc, None).await?;
         Ok(())
     }
 
     /// Retrieves single analysis result from the database in format fiendly for the client.
+    /// Without bracket result computed on all matches is returned.
     pub async fn get_result(
         &self,
         guild_id: &GuildId,
         analysis_tag: AnalysisTag,
+        bracket: &Option<String>,
     ) -> Result<String, BoxError> {
         let coll = self.db_client.collection("analysis_results");
-        let filter = doc! {"guild_id": guild_id, "tag": analysis_tag.to_string()};
+        let filter = doc! {
+            "guild_id": guild_id,
+            "tag": analysis_tag.to_string(),
+            "metadata.bracket": bracket.clone(),
+        };
         let result_doc = coll
             .find_one(filter, None)
             .await?
@@ -176,6 +224,7 @@ impl Storage {
         let res = ResultToReturn {
             guild_id: stored_result.guild_id,
             timestamp: stored_result.timestamp,
+            metadata: stored_result.metadata,
             payload: serde_json::from_str(stored_result.payload.as_str())?,
         };
         Ok(serde_json::to_string(&res)?)
@@ -0,0 +1,60 @@
+use crate::storage::Storage;
+use crate::types::MatchId;
+use crate::CONFIG;
+use chrono::Utc;
+use mongodb::options::UpdateOptions;
+use mongodb::{self, bson::doc};
+use std::collections::HashSet;
+use tokio::stream::StreamExt;
+
+/// Number of failed attempts after which match is skipped, set by "skip_match_after_attempts"
+/// config field.
+fn skip_after_attempts() -> i64 {
+    CONFIG.get_int("skip_match_after_attempts").unwrap_or(3)
+}
+
+/// Skipped matches are forgotten "skipped_match_expiry_days" after the last attempt,
+/// so they are fetched again.
+fn skipped_expiry_secs() -> i64 {
+    CONFIG.get_int("skipped_match_expiry_days").unwrap_or(30) * 86400
+}
+
+impl Storage {
+    /// Retrieves ids of matches from dotastats/skipped_matches collection, which failed at
+    /// least "skip_match_after_attempts" times. Expired entries are removed.
+    pub async fn get_skipped_match_ids(&self) -> mongodb::error::Result<HashSet<MatchId>> {
+        let coll = self.db_client.collection("skipped_matches");
+        let expired_before = Utc::now().timestamp() - skipped_expiry_secs();
+        coll.delete_many(doc! {"timestamp": {"$lt": expired_before}}, None)
+            .await?;
+        let mut cursor = coll
+            .find(doc! {"attempts": {"$gte": skip_after_attempts()}}, None)
+            .await?;
+        let mut res = HashSet::new();
+        while let Some(skipped_doc) = cursor.next().await {
+            if let Ok(match_id) = skipped_doc?.get_i64("match_id") {
+                res.insert(match_id as MatchId);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Records failed attempt of match retrieval in dotastats/skipped_matches collection, as
+    /// {match_id, reason, timestamp, attempts}, where reason and timestamp are of the last
+    /// attempt. Match is not fetched again once it fails "skip_match_after_attempts" times.
+    pub async fn add_skipped_match(
+        &self,
+        match_id: &MatchId,
+        reason: String,
+    ) -> mongodb::error::Result<()> {
+        let coll = self.db_client.collection("skipped_matches");
+        let update = doc! {
+            "$set": {"reason": reason, "timestamp": Utc::now().timestamp()},
+            "$inc": {"attempts": 1i64},
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        coll.update_one(doc! {"match_id": *match_id as i64}, update, options)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use crate::storage::Storage;
+use crate::types::{GuildId, MatchId, PlayerId};
+use chrono::Utc;
+use mongodb::{
+    self,
+    bson::{self, doc},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::stream::StreamExt;
+
+/// Last match seen of a guild member. Only matches played after it are discovered on update.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MemberSyncState {
+    pub guild_id: GuildId,
+    pub player_id: PlayerId,
+    pub last_match_id: i64,
+    pub last_start_time: i64,
+    pub timestamp: i64,
+}
+
+impl MemberSyncState {
+    pub fn new(
+        guild_id: &GuildId,
+        player_id: &PlayerId,
+        last_match_id: MatchId,
+        last_start_time: i64,
+    ) -> Self {
+        MemberSyncState {
+            guild_id: guild_id.clone(),
+            player_id: player_id.clone(),
+            last_match_id: last_match_id as i64,
+            last_start_time,
+            timestamp: Utc::now().timestamp(),
+        }
+    }
+}
+
+impl Storage {
+    /// Retrieves sync state of guild members from dotastats/member_sync_state collection.
+    pub async fn get_members_sync_state(
+        &self,
+        guild_id: &GuildId,
+    ) -> mongodb::error::Result<HashMap<PlayerId, MemberSyncState>> {
+        let coll = self.db_client.collection("member_sync_state");
+        let mut cursor = coll.find(doc! {"guild_id": guild_id}, None).await?;
+        let mut res = HashMap::new();
+        while let Some(state_doc) = cursor.next().await {
+            let state: MemberSyncState = bson::from_bson(state_doc?.into())?;
+            res.insert(state.player_id.clone(), state);
+        }
+        Ok(res)
+    }
+
+    /// Stores sync state of a single guild member.
+    pub async fn set_member_sync_state(
+        &self,
+        state: &MemberSyncState,
+    ) -> mongodb::error::Result<()> {
+        let coll = self.db_client.collection("member_sync_state");
+        let filter = doc! {"guild_id": &state.guild_id, "player_id": &state.player_id};
+        coll.delete_one(filter, None).await?;
+        coll.insert_one(bson::to_document(state)?, None).await?;
+        Ok(())
+    }
+}
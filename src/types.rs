@@ -1,3 +1,4 @@
 pub type GuildId = String;
 pub type PlayerId = String;
 pub type MatchId = u64;
+pub type AccountId = u64;